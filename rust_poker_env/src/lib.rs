@@ -1,12 +1,16 @@
 use pyo3::prelude::*;
 use rand::seq::SliceRandom;
-use rand::thread_rng;
-use pyo3::types::{PyDict, PyTuple};
+use rand_chacha::ChaCha12Rng;
+use rand::{Rng, SeedableRng};
+use rayon::prelude::*;
+use pyo3::types::{PyDict, PyList, PyTuple};
 use pyo3::ToPyObject;
-use rs_poker::core::{Hand, Rankable, Rank};
+use rs_poker::core::{Card, Hand, Rankable, Rank, Value};
+use serde::{Deserialize, Serialize};
 use std::cmp::Reverse;
+use std::collections::{BTreeMap, BTreeSet};
 
-#[derive(Debug, Clone, PartialEq)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 #[pyclass]
 pub enum Action {
     #[pyo3(name = "FOLD")]
@@ -17,20 +21,30 @@ pub enum Action {
     Call,
     #[pyo3(name = "RAISE")]
     Raise,
+    #[pyo3(name = "ALL_IN")]
+    AllIn,
 }
 
-impl ToPyObject for Action {
-    fn to_object(&self, py: Python) -> PyObject {
+impl Action {
+    /// Name of the action in the tuples exchanged with the agents
+    fn name(&self) -> &'static str {
         match self {
-            Action::Fold => "fold".to_object(py),
-            Action::Check => "check".to_object(py),
-            Action::Call => "call".to_object(py),
-            Action::Raise => "raise".to_object(py),
+            Action::Fold => "fold",
+            Action::Check => "check",
+            Action::Call => "call",
+            Action::Raise => "raise",
+            Action::AllIn => "allin",
         }
     }
 }
 
-#[derive(Debug, Clone, PartialEq)]
+impl ToPyObject for Action {
+    fn to_object(&self, py: Python) -> PyObject {
+        self.name().to_object(py)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 #[pyclass]
 pub enum Phase {
     #[pyo3(name = "PREFLOP")]
@@ -45,44 +59,773 @@ pub enum Phase {
     Showdown,
 }
 
+impl Phase {
+    /// Name of the phase in the states given to the agents
+    fn name(&self) -> &'static str {
+        match self {
+            Phase::Preflop => "preflop",
+            Phase::Flop => "flop",
+            Phase::Turn => "turn",
+            Phase::River => "river",
+            Phase::Showdown => "showdown",
+        }
+    }
+}
+
 impl ToPyObject for Phase {
     fn to_object(&self, py: Python) -> PyObject {
+        self.name().to_object(py)
+    }
+}
+
+/// Betting structure of the game, it bounds the raises offered by `get_available_actions`
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[pyclass]
+pub enum BettingMode {
+    #[pyo3(name = "NO_LIMIT")]
+    NoLimit,
+    #[pyo3(name = "POT_LIMIT")]
+    PotLimit,
+    #[pyo3(name = "FIXED_LIMIT")]
+    FixedLimit,
+}
+
+/// Format of the cards in the states given by `get_state`
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[pyclass]
+pub enum Encoding {
+    /// Card strings, e.g. ["Ah", "Td"]
+    #[pyo3(name = "CARDS")]
+    Cards,
+    /// Vectors of 52 floats, 1.0 at the index of each card (see `card_to_index`)
+    #[pyo3(name = "ONE_HOT")]
+    OneHot,
+}
+
+/// What a player sees of the others' chips in `get_state` and `get_observation_vector`
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[pyclass]
+pub enum ObservationLevel {
+    /// Every stack
+    #[pyo3(name = "FULL")]
+    Full,
+    /// His own stack only, the others' hidden (None, 0.0 in the vector) while their bets and
+    /// all in flags stay visible
+    #[pyo3(name = "COMMITTED_ONLY")]
+    CommittedOnly,
+}
+
+/// Rewards given by `step`
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[pyclass]
+pub enum RewardMode {
+    /// Nothing until the end of the hand, then the net chips of the acting player
+    #[pyo3(name = "SPARSE")]
+    Sparse,
+    /// Each step, the change of what the acting player expects to get back from the pot at his
+    /// all in equity, less what he put in. Summed over his steps, it gives his net chips,
+    /// but the rewards of the players for a step don't sum to zero.
+    #[pyo3(name = "EQUITY_SHAPED")]
+    EquityShaped,
+}
+
+/// Units of the rewards given by `step`
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[pyclass]
+pub enum RewardScale {
+    /// Chips won or lost
+    #[pyo3(name = "CHIPS")]
+    Chips,
+    /// Chips divided by the big blind of the current level
+    #[pyo3(name = "BIG_BLINDS")]
+    BigBlinds,
+    /// Chips divided by the initial stack
+    #[pyo3(name = "STARTING_STACK")]
+    StartingStack,
+}
+
+/// Who posts the ante of the blind schedule
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[pyclass]
+pub enum AnteMode {
+    /// No ante at all
+    #[pyo3(name = "NONE")]
+    None,
+    /// Every player posts the ante
+    #[pyo3(name = "PER_PLAYER")]
+    PerPlayer,
+    /// The big blind alone posts the ante, once for the table, after his blind
+    #[pyo3(name = "BIG_BLIND_ANTE")]
+    BigBlindAnte,
+}
+
+/// Fixed strategy played in Rust, without calling any Python agent: by `simulate_batch`, or
+/// by `step_bid` for a seat given one instead of an agent
+#[derive(Debug, Clone, PartialEq)]
+#[pyclass]
+pub enum NativeStrategy {
+    /// Check when possible, fold otherwise
+    #[pyo3(name = "ALWAYS_FOLD")]
+    AlwaysFold,
+    /// Check when possible, call otherwise
+    #[pyo3(name = "ALWAYS_CALL")]
+    AlwaysCall,
+    /// Any legal action, raises of a random amount in the allowed range
+    #[pyo3(name = "RANDOM")]
+    Random,
+    /// Min-raise strong hands, check or call medium ones and give up on the rest
+    #[pyo3(name = "TIGHT_AGGRESSIVE")]
+    TightAggressive,
+    /// All in with a starting hand worth the threshold by `preflop_strength`, check or fold
+    /// otherwise. With more than two hole cards, all in with a strong hand only.
+    #[pyo3(name = "SHOVE_OR_FOLD")]
+    ShoveOrFold,
+}
+
+impl NativeStrategy {
+    /// Name of the strategy as seen from Python
+    fn name(&self) -> &'static str {
+        match self {
+            NativeStrategy::AlwaysFold => "ALWAYS_FOLD",
+            NativeStrategy::AlwaysCall => "ALWAYS_CALL",
+            NativeStrategy::Random => "RANDOM",
+            NativeStrategy::TightAggressive => "TIGHT_AGGRESSIVE",
+            NativeStrategy::ShoveOrFold => "SHOVE_OR_FOLD",
+        }
+    }
+
+    /// Choose the action of the current player of `env`, `threshold` being the one of ShoveOrFold.
+    /// Random draws from `rng`, the env's own so that a seeded run plays the same again.
+    fn choose(&self, env: &PokerEnv, threshold: f64, rng: &mut ChaCha12Rng) -> PyResult<(Action, i32)> {
+        let legal = env.legal_actions();
+        let passive = legal.iter().find_map(|action| match action {
+            LegalAction::Check => Some((Action::Check, 0)),
+            LegalAction::Call(amount) => Some((Action::Call, *amount)),
+            _ => None,
+        });
+        let min_raise = legal.iter().find_map(|action| match action {
+            LegalAction::Raise(min, _) => Some((Action::Raise, *min)),
+            _ => None,
+        });
+        let check_or_fold = if legal.contains(&LegalAction::Check) {
+            (Action::Check, 0)
+        } else {
+            (Action::Fold, 0)
+        };
+
+        let action = match self {
+            NativeStrategy::AlwaysFold => check_or_fold,
+            NativeStrategy::AlwaysCall => passive.unwrap_or((Action::Fold, 0)),
+            NativeStrategy::Random => {
+                match legal[rng.gen_range(0..legal.len())] {
+                    LegalAction::Fold => (Action::Fold, 0),
+                    LegalAction::Check => (Action::Check, 0),
+                    LegalAction::Call(amount) => (Action::Call, amount),
+                    LegalAction::Raise(min, max) => (Action::Raise, rng.gen_range(min..=max)),
+                    LegalAction::AllIn(amount) => (Action::AllIn, amount),
+                }
+            }
+            NativeStrategy::TightAggressive => match env.hand_strength(env.current_player)? {
+                2 => min_raise.or(passive).unwrap_or((Action::Fold, 0)),
+                1 => passive.unwrap_or((Action::Fold, 0)),
+                _ => check_or_fold,
+            },
+            NativeStrategy::ShoveOrFold => {
+                let hole = &env.player_cards[env.current_player];
+                let strong = if hole.len() == 2 {
+                    preflop_strength(hole.clone())? >= threshold
+                } else {
+                    env.hand_strength(env.current_player)? == 2
+                };
+                let shove = legal.iter().find_map(|action| match action {
+                    LegalAction::AllIn(amount) => Some((Action::AllIn, *amount)),
+                    _ => None,
+                });
+                match strong {
+                    true => shove.or(passive).unwrap_or(check_or_fold),
+                    false => check_or_fold,
+                }
+            }
+        };
+        Ok(action)
+    }
+}
+
+/// Starting hand equity from which ShoveOrFold goes all in, unless a seat sets its own
+const SHOVE_THRESHOLD: f64 = 0.6;
+
+/// Seat played in Rust by `step_bid` with a native strategy, and the threshold of ShoveOrFold.
+/// A bare `NativeStrategy` can also be given as an agent.
+#[derive(Debug, Clone)]
+#[pyclass]
+pub struct NativeAgent {
+    #[pyo3(get, set)]
+    strategy: NativeStrategy,
+    #[pyo3(get, set)]
+    threshold: f64,
+}
+
+#[pymethods]
+impl NativeAgent {
+    #[new]
+    #[pyo3(signature = (strategy, threshold=SHOVE_THRESHOLD))]
+    pub fn new(strategy: NativeStrategy, threshold: f64) -> Self {
+        NativeAgent { strategy, threshold }
+    }
+}
+
+/// Cumulative statistics of a player over the hands resolved
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+struct PlayerStats {
+    hands: u32,
+    /// Hands where he voluntarily put chips in the pot preflop (blinds excluded)
+    vpip: u32,
+    /// Hands where he raised preflop
+    pfr: u32,
+    /// Hands he ended with more chips than he started with
+    won: u32,
+    net_chips: i64,
+}
+
+/// Net chips won by each seat in one game of `simulate_batch`, returned by the worker that
+/// played it on its own copy of the env
+struct GameResult {
+    rewards: Vec<i32>,
+}
+
+/// Totals of the games of `simulate_batch` by seat. Each worker folds the results of its games
+/// into its own totals, merged once all of them are done.
+#[derive(Debug, Clone)]
+struct BatchStats {
+    games: usize,
+    rewards: Vec<i64>,
+    wins: Vec<usize>,
+}
+
+impl BatchStats {
+    fn new(num_players: usize) -> Self {
+        BatchStats { games: 0, rewards: vec![0; num_players], wins: vec![0; num_players] }
+    }
+
+    fn add(mut self, result: GameResult) -> Self {
+        self.games += 1;
+        for (seat, &reward) in result.rewards.iter().enumerate() {
+            self.rewards[seat] += reward as i64;
+            if reward > 0 {
+                self.wins[seat] += 1;
+            }
+        }
+        self
+    }
+
+    fn merge(mut self, other: BatchStats) -> Self {
+        self.games += other.games;
+        for seat in 0..self.rewards.len() {
+            self.rewards[seat] += other.rewards[seat];
+            self.wins[seat] += other.wins[seat];
+        }
+        self
+    }
+}
+
+/// Discrete actions for solvers, turned into legal amounts by `concrete_action`
+#[derive(Debug, Clone, PartialEq)]
+#[pyclass]
+pub enum AbstractAction {
+    #[pyo3(name = "FOLD")]
+    Fold,
+    #[pyo3(name = "CHECK_CALL")]
+    CheckCall,
+    /// Raise of half the pot once the call is made
+    #[pyo3(name = "HALF_POT")]
+    HalfPot,
+    #[pyo3(name = "POT")]
+    Pot,
+    #[pyo3(name = "ALL_IN")]
+    AllIn,
+}
+
+/// Action the current player is allowed to take, with its amount or raise range
+#[derive(Debug, Clone, PartialEq)]
+enum LegalAction {
+    Fold,
+    Check,
+    Call(i32),
+    Raise(i32, i32),
+    AllIn(i32),
+}
+
+/// Something that happened during a hand, kept in the history when `record_history` is set
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub enum Event {
+    PhaseStart { phase: Phase },
+    PlayerAction { player: String, action: Action, amount: i32 },
+    BoardDealt { cards: Vec<String> },
+    PotAwarded { pot: usize, player: String, amount: i32 },
+    UncalledBet { player: String, amount: i32 },
+    Pots { pots: Vec<i32>, players: Vec<Vec<String>> },
+    PlayerEliminated { player: String },
+    /// Everyone folded to the big blind, who wins the blinds
+    Walk { player: String },
+    /// Chips added to a stack by `auto_topup_to`, from outside the table
+    TopUp { player: String, amount: i32 },
+    /// Hole cards revealed at showdown
+    HandShown { player: String, cards: Vec<String> },
+    Stacks { stacks: Vec<i32> },
+    // Only logged, never kept in the history
+    Progress { episode: i32, episodes: i32 },
+    State { state: String },
+}
+
+impl Event {
+    /// Dict of the event, its kind under "type"
+    fn to_dict(&self, py: Python) -> PyResult<Py<PyDict>> {
+        let dict = PyDict::new_bound(py);
+        match self {
+            Event::PhaseStart { phase } => {
+                dict.set_item("type", "phase_start")?;
+                dict.set_item("phase", phase)?;
+            }
+            Event::PlayerAction { player, action, amount } => {
+                dict.set_item("type", "player_action")?;
+                dict.set_item("player", player)?;
+                dict.set_item("action", action)?;
+                dict.set_item("amount", amount)?;
+            }
+            Event::BoardDealt { cards } => {
+                dict.set_item("type", "board_dealt")?;
+                dict.set_item("cards", cards.clone())?;
+            }
+            Event::PotAwarded { pot, player, amount } => {
+                dict.set_item("type", "pot_awarded")?;
+                dict.set_item("pot", pot)?;
+                dict.set_item("player", player)?;
+                dict.set_item("amount", amount)?;
+            }
+            Event::UncalledBet { player, amount } => {
+                dict.set_item("type", "uncalled_bet")?;
+                dict.set_item("player", player)?;
+                dict.set_item("amount", amount)?;
+            }
+            Event::Pots { pots, players } => {
+                dict.set_item("type", "pots")?;
+                dict.set_item("pots", pots.clone())?;
+                dict.set_item("players", players.clone())?;
+            }
+            Event::PlayerEliminated { player } => {
+                dict.set_item("type", "player_eliminated")?;
+                dict.set_item("player", player)?;
+            }
+            Event::Walk { player } => {
+                dict.set_item("type", "walk")?;
+                dict.set_item("player", player)?;
+            }
+            Event::TopUp { player, amount } => {
+                dict.set_item("type", "top_up")?;
+                dict.set_item("player", player)?;
+                dict.set_item("amount", amount)?;
+            }
+            Event::HandShown { player, cards } => {
+                dict.set_item("type", "hand_shown")?;
+                dict.set_item("player", player)?;
+                dict.set_item("cards", cards.clone())?;
+            }
+            Event::Stacks { stacks } => {
+                dict.set_item("type", "stacks")?;
+                dict.set_item("stacks", stacks.clone())?;
+            }
+            Event::Progress { episode, episodes } => {
+                dict.set_item("type", "progress")?;
+                dict.set_item("episode", episode)?;
+                dict.set_item("episodes", episodes)?;
+            }
+            Event::State { state } => {
+                dict.set_item("type", "state")?;
+                dict.set_item("state", state)?;
+            }
+        }
+        Ok(dict.into())
+    }
+
+    /// Logging level of the event: the play itself is "info", the dumps of the table "debug"
+    fn level(&self) -> &'static str {
+        match self {
+            Event::Pots { .. } | Event::Stacks { .. } | Event::State { .. } => "debug",
+            _ => "info",
+        }
+    }
+}
+
+impl std::fmt::Display for Event {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
         match self {
-            Phase::Preflop => "preflop".to_object(py),
-            Phase::Flop => "flop".to_object(py),
-            Phase::Turn => "turn".to_object(py),
-            Phase::River => "river".to_object(py),
-            Phase::Showdown => "showdown".to_object(py),
+            Event::PhaseStart { phase } => write!(f, "Start of {:?}", phase),
+            Event::PlayerAction { player, action, amount } => match action {
+                Action::Fold | Action::Check => write!(f, "{} has {}", player, action.name()),
+                _ => write!(f, "{} has {} {}", player, action.name(), amount),
+            },
+            Event::BoardDealt { cards } => write!(f, "Board dealt: {:?}", cards),
+            Event::PotAwarded { pot, player, amount } => write!(f, "Winner pot {}: {} takes {}", pot, player, amount),
+            Event::UncalledBet { player, amount } => write!(f, "Uncalled bet of {} returned to {}", amount, player),
+            Event::Pots { pots, players } => write!(f, "pots: {:?}\npots_player: {:?}", pots, players),
+            Event::PlayerEliminated { player } => write!(f, "{} lost", player),
+            Event::Walk { player } => write!(f, "Walk for {}", player),
+            Event::TopUp { player, amount } => write!(f, "{} tops up {}", player, amount),
+            Event::HandShown { player, cards } => write!(f, "{} shows {:?}", player, cards),
+            Event::Stacks { stacks } => {
+                write!(f, "State of stacks: {:?}\n{} player remaining", stacks, stacks.len())
+            }
+            Event::Progress { episode, episodes } => write!(f, "episode {} on {}", episode, episodes),
+            Event::State { state } => write!(f, "{}", state),
         }
     }
 }
 
+/// Options of a `PokerEnv` with their defaults (blinds of 1 and 2 and stacks of 100 big blinds),
+/// to build the config once and set the fields by name
+#[derive(Clone)]
 #[pyclass]
+pub struct PokerConfig {
+    #[pyo3(get, set)]
+    small_blind: i32,
+    #[pyo3(get, set)]
+    big_blind: i32,
+    #[pyo3(get, set)]
+    initial_stack: i32,
+    #[pyo3(get, set)]
+    betting_mode: BettingMode,
+    #[pyo3(get, set)]
+    raise_cap: usize,
+    #[pyo3(get, set)]
+    hole_cards: usize,
+    #[pyo3(get, set)]
+    short_deck: bool,
+    #[pyo3(get, set)]
+    record_history: bool,
+    #[pyo3(get, set)]
+    encoding: Encoding,
+    #[pyo3(get, set)]
+    names: Option<Vec<String>>,
+    #[pyo3(get, set)]
+    blind_schedule: Option<Vec<(u32, i32, i32, i32)>>,
+    #[pyo3(get, set)]
+    run_it_twice: bool,
+    #[pyo3(get, set)]
+    max_raises_per_street: Option<usize>,
+    #[pyo3(get, set)]
+    dealer_pos: Option<usize>,
+    #[pyo3(get, set)]
+    rotate_button: bool,
+    #[pyo3(get, set)]
+    log_callback: Option<PyObject>,
+    #[pyo3(get, set)]
+    rake_percent: f64,
+    #[pyo3(get, set)]
+    rake_cap: Option<i32>,
+    #[pyo3(get, set)]
+    burn_cards: bool,
+    #[pyo3(get, set)]
+    agent_method: String,
+    #[pyo3(get, set)]
+    payouts: Option<Vec<f64>>,
+    #[pyo3(get, set)]
+    muck_losers: bool,
+    #[pyo3(get, set)]
+    on_hand_start: Option<PyObject>,
+    #[pyo3(get, set)]
+    on_phase_change: Option<PyObject>,
+    #[pyo3(get, set)]
+    board_schedule: Option<Vec<usize>>,
+    #[pyo3(get, set)]
+    reward_mode: RewardMode,
+    #[pyo3(get, set)]
+    equity_iterations: usize,
+    #[pyo3(get, set)]
+    auto_topup_to: Option<i32>,
+    #[pyo3(get, set)]
+    max_actions_per_round: usize,
+    #[pyo3(get, set)]
+    observation_level: ObservationLevel,
+    #[pyo3(get, set)]
+    seed: Option<u64>,
+    #[pyo3(get, set)]
+    allin_ev_rewards: bool,
+    #[pyo3(get, set)]
+    ante_mode: AnteMode,
+    #[pyo3(get, set)]
+    debug_invariants: bool,
+    #[pyo3(get, set)]
+    straddle: Option<i32>,
+    #[pyo3(get, set)]
+    stacks: Option<Vec<i32>>,
+    #[pyo3(get, set)]
+    mark_allin_calls: bool,
+    #[pyo3(get, set)]
+    face_up_cards: usize,
+    #[pyo3(get, set)]
+    reward_scale: RewardScale,
+}
+
+#[pymethods]
+impl PokerConfig {
+    #[new]
+    #[allow(clippy::too_many_arguments)]
+    #[pyo3(signature = (small_blind=1, big_blind=2, initial_stack=200, betting_mode=BettingMode::NoLimit, raise_cap=4, hole_cards=2, short_deck=false, record_history=false, encoding=Encoding::Cards, names=None, blind_schedule=None, run_it_twice=false, max_raises_per_street=None, dealer_pos=None, rotate_button=true, log_callback=None, rake_percent=0.0, rake_cap=None, burn_cards=false, agent_method="choose_action".to_string(), payouts=None, muck_losers=false, on_hand_start=None, on_phase_change=None, board_schedule=None, reward_mode=RewardMode::Sparse, equity_iterations=200, auto_topup_to=None, max_actions_per_round=1000, observation_level=ObservationLevel::Full, seed=None, allin_ev_rewards=false, ante_mode=AnteMode::PerPlayer, debug_invariants=false, straddle=None, stacks=None, mark_allin_calls=false, face_up_cards=0, reward_scale=RewardScale::Chips))]
+    pub fn new(
+        small_blind: i32,
+        big_blind: i32,
+        initial_stack: i32,
+        betting_mode: BettingMode,
+        raise_cap: usize,
+        hole_cards: usize,
+        short_deck: bool,
+        record_history: bool,
+        encoding: Encoding,
+        names: Option<Vec<String>>,
+        blind_schedule: Option<Vec<(u32, i32, i32, i32)>>,
+        run_it_twice: bool,
+        max_raises_per_street: Option<usize>,
+        dealer_pos: Option<usize>,
+        rotate_button: bool,
+        log_callback: Option<PyObject>,
+        rake_percent: f64,
+        rake_cap: Option<i32>,
+        burn_cards: bool,
+        agent_method: String,
+        payouts: Option<Vec<f64>>,
+        muck_losers: bool,
+        on_hand_start: Option<PyObject>,
+        on_phase_change: Option<PyObject>,
+        board_schedule: Option<Vec<usize>>,
+        reward_mode: RewardMode,
+        equity_iterations: usize,
+        auto_topup_to: Option<i32>,
+        max_actions_per_round: usize,
+        observation_level: ObservationLevel,
+        seed: Option<u64>,
+        allin_ev_rewards: bool,
+        ante_mode: AnteMode,
+        debug_invariants: bool,
+        straddle: Option<i32>,
+        stacks: Option<Vec<i32>>,
+        mark_allin_calls: bool,
+        face_up_cards: usize,
+        reward_scale: RewardScale,
+    ) -> Self {
+        PokerConfig {
+            small_blind,
+            big_blind,
+            initial_stack,
+            betting_mode,
+            raise_cap,
+            hole_cards,
+            short_deck,
+            record_history,
+            encoding,
+            names,
+            blind_schedule,
+            run_it_twice,
+            max_raises_per_street,
+            dealer_pos,
+            rotate_button,
+            log_callback,
+            rake_percent,
+            rake_cap,
+            burn_cards,
+            agent_method,
+            payouts,
+            muck_losers,
+            on_hand_start,
+            on_phase_change,
+            board_schedule,
+            reward_mode,
+            equity_iterations,
+            auto_topup_to,
+            max_actions_per_round,
+            observation_level,
+            seed,
+            allin_ev_rewards,
+            ante_mode,
+            debug_invariants,
+            straddle,
+            stacks,
+            mark_allin_calls,
+            face_up_cards,
+            reward_scale,
+        }
+    }
+}
+
+/// What a hand changes of the env, saved by `save_checkpoint`: the deck and the cards, the chips,
+/// the flags and the turn of the players, and the seats and totals the resolution updates. The
+/// history is only saved as its length, a rollback dropping the events recorded since.
+#[derive(Clone)]
+struct Checkpoint {
+    hand_number: u64,
+    history_len: usize,
+    agents: Vec<PyObject>,
+    dead_agents: Vec<PyObject>,
+    names: Vec<String>,
+    dead_names: Vec<String>,
+    finishing_order: Vec<String>,
+    num_players: usize,
+    dealer_pos: usize,
+    dead_small_blind_next: bool,
+    hands_played: u32,
+    rake_collected: i64,
+    stats: BTreeMap<String, PlayerStats>,
+    deck: Vec<String>,
+    burned: Vec<String>,
+    rng: ChaCha12Rng,
+    player_cards: Vec<Vec<String>>,
+    community_cards: Vec<String>,
+    second_board: Vec<String>,
+    runout_from: Option<usize>,
+    stacks: Vec<i32>,
+    bets: Vec<i32>,
+    pot: i32,
+    contributions: Vec<i32>,
+    folded: Vec<bool>,
+    all_in: Vec<bool>,
+    has_acted_this_round: Vec<bool>,
+    voluntary: Vec<bool>,
+    raised_preflop: Vec<bool>,
+    may_raise: Vec<bool>,
+    rewards: Vec<i32>,
+    shaping_potential: Vec<i32>,
+    shown_hands: Vec<(String, Vec<String>)>,
+    terminal_states: Vec<(String, i32, Py<PyDict>)>,
+    current_phase: Phase,
+    current_player: usize,
+    max_raise: i32,
+    raises_this_street: usize,
+    num_raises_this_hand: usize,
+    last_aggressor: Option<usize>,
+    actions_this_street: usize,
+    hand_actions: Vec<(usize, String, i32)>,
+    street_starts: Vec<usize>,
+}
+
+/// Index of a pot and its winners, each with the chips he was given
+type PotAward = (usize, Vec<(String, i32)>);
+
+#[pyclass(module = "rust_poker_env")]
+#[derive(Clone, Serialize, Deserialize)]
 pub struct PokerEnv {
     #[pyo3(get, set)]
+    #[serde(skip)]
     agents: Vec<PyObject>,
     #[pyo3(get, set)]
+    #[serde(skip)]
     dead_agents: Vec<PyObject>,
     #[pyo3(get, set)]
     names: Vec<String>,
     #[pyo3(get, set)]
     dead_names: Vec<String>,
     #[pyo3(get)]
+    finishing_order: Vec<String>,
+    #[pyo3(get)]
     num_players: usize,
     #[pyo3(get)]
     small_blind: i32,
     #[pyo3(get)]
     big_blind: i32,
     #[pyo3(get)]
+    ante: i32,
+    #[pyo3(get, set)]
+    ante_mode: AnteMode,
+    dead_ante: i32,
+    #[pyo3(get, set)]
+    straddle: Option<i32>,
+    straddled: bool,
+    #[pyo3(get)]
+    blind_schedule: Vec<(u32, i32, i32, i32)>,
+    #[pyo3(get)]
+    hands_played: u32,
+    #[pyo3(get)]
+    hand_number: u64,
+    #[pyo3(get)]
     max_raise: i32,
     #[pyo3(get)]
     initial_stack: i32,
+    #[pyo3(get)]
+    initial_stacks: BTreeMap<String, i32>,
+    #[pyo3(get)]
+    betting_mode: BettingMode,
+    #[pyo3(get)]
+    raise_cap: usize,
+    #[pyo3(get, set)]
+    max_raises_per_street: Option<usize>,
+    #[pyo3(get)]
+    rake_percent: f64,
+    #[pyo3(get)]
+    rake_cap: Option<i32>,
+    #[pyo3(get)]
+    rake_collected: i64,
+    #[pyo3(get)]
+    payouts: Option<Vec<f64>>,
+    #[pyo3(get)]
+    board_schedule: Vec<usize>,
+    #[pyo3(get)]
+    reward_mode: RewardMode,
+    #[pyo3(get, set)]
+    reward_scale: RewardScale,
+    #[pyo3(get, set)]
+    auto_topup_to: Option<i32>,
+    #[pyo3(get)]
+    chips_added: i64,
+    #[pyo3(get, set)]
+    equity_iterations: usize,
+    shaping_potential: Vec<i32>,
+    #[serde(skip)]
+    checkpoints: Vec<Checkpoint>,
+    #[pyo3(get, set)]
+    muck_losers: bool,
+    #[pyo3(get, set)]
+    allin_ev_rewards: bool,
+    #[pyo3(get, set)]
+    debug_invariants: bool,
+    #[pyo3(get, set)]
+    mark_allin_calls: bool,
+    #[pyo3(get)]
+    shown_hands: Vec<(String, Vec<String>)>,
+    #[pyo3(get)]
+    #[serde(skip)]
+    terminal_states: Vec<(String, i32, Py<PyDict>)>,
+    #[pyo3(get, set)]
+    raises_this_street: usize,
+    #[pyo3(get)]
+    num_raises_this_hand: usize,
+    #[pyo3(get)]
+    last_aggressor: Option<usize>,
+    #[pyo3(get, set)]
+    max_actions_per_round: usize,
+    actions_this_street: usize,
+    #[pyo3(get)]
+    hole_cards: usize,
+    #[pyo3(get)]
+    face_up_cards: usize,
+    #[pyo3(get)]
+    short_deck: bool,
+    #[pyo3(get, set)]
+    encoding: Encoding,
+    #[pyo3(get, set)]
+    observation_level: ObservationLevel,
     #[pyo3(get, set)]
     stacks: Vec<i32>,
     #[pyo3(get, set)]
     dealer_pos: usize,
     #[pyo3(get, set)]
+    rotate_button: bool,
+    #[pyo3(get)]
+    dead_small_blind: bool,
+    dead_small_blind_next: bool,
+    #[pyo3(get, set)]
     bets: Vec<i32>,
+    #[pyo3(get)]
+    pot: i32,
+    #[pyo3(get)]
+    contributions: Vec<i32>,
     #[pyo3(get, set)]
     folded: Vec<bool>,
     #[pyo3(get, set)]
@@ -91,535 +834,5233 @@ pub struct PokerEnv {
     rewards: Vec<i32>,
     #[pyo3(get, set)]
     current_phase: Phase,
-    #[pyo3(get, set)]
+    #[pyo3(get)]
     current_player: usize,
-    #[pyo3(get, set)]
+    hand_start_stacks: Vec<i32>,
+    #[pyo3(get)]
     deck: Vec<String>,
-    #[pyo3(get, set)]
+    base_deck: Vec<String>,
+    #[serde(skip)]
+    sorted_deck: Vec<String>,
+    #[pyo3(get)]
+    burn_cards: bool,
+    #[pyo3(get)]
+    burned: Vec<String>,
+    rigged_deck: Option<Vec<String>>,
+    // Saved with the env, so that a restored env deals on as the original would
+    #[serde(default = "ChaCha12Rng::from_entropy")]
+    rng: ChaCha12Rng,
+    #[pyo3(get)]
     player_cards: Vec<Vec<String>>,
-    #[pyo3(get, set)]
+    #[pyo3(get)]
     community_cards: Vec<String>,
-}
-
-#[pymethods]
-impl PokerEnv {
-    #[new]
-    /// Init poker env
-    pub fn new(
-        _py: Python,
-        agents: Vec<PyObject>,
+    hand_actions: Vec<(usize, String, i32)>,
+    street_starts: Vec<usize>,
+    voluntary: Vec<bool>,
+    raised_preflop: Vec<bool>,
+    may_raise: Vec<bool>,
+    #[pyo3(get)]
+    has_acted_this_round: Vec<bool>,
+    stats: BTreeMap<String, PlayerStats>,
+    #[pyo3(get, set)]
+    run_it_twice: bool,
+    runout_from: Option<usize>,
+    #[pyo3(get)]
+    second_board: Vec<String>,
+    #[pyo3(get, set)]
+    agent_method: String,
+    #[pyo3(get, set)]
+    #[serde(skip)]
+    log_callback: Option<PyObject>,
+    #[pyo3(get, set)]
+    #[serde(skip)]
+    on_hand_start: Option<PyObject>,
+    #[pyo3(get, set)]
+    #[serde(skip)]
+    on_phase_change: Option<PyObject>,
+    #[pyo3(get, set)]
+    record_history: bool,
+    history: Vec<(u64, Event)>,
+}
+
+#[pymethods]
+impl PokerEnv {
+    #[new]
+    #[pyo3(signature = (agents, small_blind, big_blind, initial_stack, config=None))]
+    /// Init poker env, with the other options of a `PokerConfig` if one is given
+    pub fn new(
+        py: Python,
+        agents: Vec<PyObject>,
         small_blind: i32,
         big_blind: i32,
         initial_stack: i32,
+        config: Option<PokerConfig>,
     ) -> PyResult<Self> {
-        let num_players = agents.len();
-        let mut poker_env = PokerEnv {
-            agents: agents.clone(),
-            dead_agents: Vec::new(),
-            num_players: agents.len(),
-            names: (0..num_players).map(|i| format!("player_{}", (b'A' + i as u8) as char)).collect(),
-            dead_names: Vec::new(),
-            small_blind,
-            big_blind,
-            max_raise: 0,
-            initial_stack,
-            stacks: vec![initial_stack; num_players],
-            dealer_pos: 0,
-            bets: vec![0; num_players],
-            folded: vec![false; num_players],
-            all_in: vec![false; num_players],
-            rewards: vec![0; num_players],
-            current_phase: Phase::Preflop,
-            current_player: 0,
-            deck: Vec::new(),
-            player_cards: vec![Vec::new(); num_players],
-            community_cards: Vec::new(),
+        let mut config = match config {
+            Some(config) => config,
+            // The defaults are the ones of the constructor of `PokerConfig`
+            None => py.get_type_bound::<PokerConfig>().call0()?.extract()?,
         };
+        config.small_blind = small_blind;
+        config.big_blind = big_blind;
+        config.initial_stack = initial_stack;
+        Self::build(agents, config)
+    }
 
-        poker_env.reset()?;
-        Ok(poker_env)
+    /// Build an env from a `PokerConfig` alone, its blinds and initial stack included
+    #[staticmethod]
+    pub fn from_config(agents: Vec<PyObject>, config: PokerConfig) -> PyResult<Self> {
+        Self::build(agents, config)
     }
 
-    /// Reset the env for a new round
-    pub fn reset(&mut self) -> PyResult<()> {
-        // Reset game state
-        self.bets = vec![0; self.num_players];
-        self.folded = vec![false; self.num_players];
-        self.all_in = vec![false; self.num_players];
-        self.rewards = vec![0; self.num_players];
+    /// Reset the env for a new round. `on_hand_start` is then called with the number of the
+    /// hand and the seat of the dealer.
+    /// After eliminations the button follows the big blind, which always moves to the next
+    /// player: the small blind of a busted player is dead, and the button stays on the player
+    /// before a busted small blind.
+    /// With `reset_stacks`, the players at the table start the hand with their starting stacks
+    /// again, for independent hands; players eliminated stay out until `revive`.
+    #[pyo3(signature = (reset_stacks=false))]
+    pub fn reset(&mut self, reset_stacks: bool) -> PyResult<()> {
+        // A hand abandoned before its resolution gives back the chips put in the pot (antes included)
+        if self.pot > 0 {
+            for i in 0..self.num_players {
+                self.stacks[i] += self.contributions[i];
+            }
+        }
+        if reset_stacks {
+            self.stacks = self.names.iter().map(|name| self.starting_stack(name)).collect();
+        }
+        // Every deal gets the next number from 1, over the life of the env and revivals included
+        self.hand_number += 1;
+
+        // Cash game top-ups, the chips coming from outside the table. Busted players have left.
+        if let Some(target) = self.auto_topup_to {
+            for i in 0..self.num_players {
+                let amount = target - self.stacks[i];
+                if amount > 0 {
+                    self.stacks[i] += amount;
+                    self.chips_added += amount as i64;
+                    self.emit(Event::TopUp { player: self.names[i].clone(), amount }, false)?;
+                }
+            }
+        }
+
+        // Reset game state, reusing the allocations of the previous hand
+        let n = self.num_players;
+        self.bets.clear();
+        self.bets.resize(n, 0);
+        self.pot = 0;
+        self.contributions.clear();
+        self.contributions.resize(n, 0);
+        self.folded.clear();
+        self.folded.resize(n, false);
+        self.all_in.clear();
+        self.all_in.resize(n, false);
+        self.rewards.clear();
+        self.rewards.resize(n, 0);
+        self.voluntary.clear();
+        self.voluntary.resize(n, false);
+        self.raised_preflop.clear();
+        self.raised_preflop.resize(n, false);
+        self.may_raise.clear();
+        self.may_raise.resize(n, true);
+        self.has_acted_this_round.clear();
+        self.has_acted_this_round.resize(n, false);
+        self.shown_hands.clear();
+        self.shaping_potential.clear();
+        self.shaping_potential.resize(n, 0);
+        self.terminal_states.clear();
         self.current_phase = Phase::Preflop;
-        self.dealer_pos = (self.dealer_pos + 1) % self.num_players;
-        self.current_player = (self.dealer_pos + 3) % self.num_players;
+        self.emit(Event::PhaseStart { phase: Phase::Preflop }, false)?;
+        self.raises_this_street = 0;
+        self.actions_this_street = 0;
+        self.num_raises_this_hand = 0;
+        self.last_aggressor = None;
+        if self.rotate_button {
+            self.dealer_pos = (self.dealer_pos + 1) % self.num_players;
+        }
+        self.dead_small_blind = std::mem::take(&mut self.dead_small_blind_next) && self.num_players > 2;
+        self.current_player = self.preflop_first_pos();
+        self.hand_start_stacks.clone_from(&self.stacks);
 
-        // Create and shuffle deck
-        let ranks = vec!["2", "3", "4", "5", "6", "7", "8", "9", "T", "J", "Q", "K", "A"];
-        let suits = vec!["h", "d", "c", "s"];
-        self.deck = ranks
-            .iter()
-            .flat_map(|&rank| suits.iter().map(move |&suit| format!("{}{}", rank, suit)))
-            .collect::<Vec<String>>();
-        self.deck.shuffle(&mut thread_rng());
+        // Shuffle a copy of the base deck, unless one was rigged with rig_next_deal
+        if let Some(deck) = self.rigged_deck.take() {
+            self.deck = deck;
+        } else {
+            self.deck.clone_from(&self.base_deck);
+            self.deck.shuffle(&mut self.rng);
+        }
+        if sorted_cards(&self.deck) != self.sorted_deck {
+            return Err(PyErr::new::<pyo3::exceptions::PyRuntimeError, _>(format!(
+                "Error: the deck to deal has {} cards but isn't the {} of the game",
+                self.deck.len(),
+                self.sorted_deck.len()
+            )));
+        }
 
         // Distribute private cards
-        self.player_cards = vec![Vec::new(); self.num_players];
-        for i in 0..self.num_players {
-            self.player_cards[i] = vec![
-                self.deck.pop().ok_or_else(|| PyErr::new::<pyo3::exceptions::PyValueError, _>("Deck is empty"))?,
-                self.deck.pop().ok_or_else(|| PyErr::new::<pyo3::exceptions::PyValueError, _>("Deck is empty"))?,
-            ];
+        self.player_cards.resize(n, Vec::new());
+        for i in 0..n {
+            self.player_cards[i].clear();
+            for _ in 0..self.hole_cards {
+                let card = self.deck.pop().ok_or_else(|| PyErr::new::<pyo3::exceptions::PyValueError, _>("Deck is empty"))?;
+                self.player_cards[i].push(card);
+            }
         }
 
         // Reset community cards
-        self.community_cards = Vec::new();
+        self.community_cards.clear();
+        self.second_board.clear();
+        self.burned.clear();
+        self.runout_from = None;
+
+        // Blinds and ante of the level reached, then the count includes this hand
+        if let Some(&(_, small_blind, big_blind, ante)) =
+            self.blind_schedule.iter().rev().find(|level| level.0 <= self.hands_played)
+        {
+            self.small_blind = small_blind;
+            self.big_blind = big_blind;
+            self.ante = ante;
+        }
+        self.hands_played += 1;
+
+        // Force antes, straight into the pot
+        self.hand_actions.clear();
+        self.street_starts.clear();
+        self.dead_ante = 0;
+        if self.ante > 0 && self.ante_mode == AnteMode::PerPlayer {
+            for i in 0..n {
+                let ante = self.ante.min(self.stacks[i]);
+                self.stacks[i] -= ante;
+                self.contributions[i] += ante;
+                self.pot += ante;
+                if self.stacks[i] == 0 {
+                    self.all_in[i] = true;
+                }
+                self.hand_actions.push((i, "ante".to_string(), ante));
+            }
+        }
+
+        // Force blinds. A player who can't cover his blind posts all in what he has, and
+        // resolution then gives him a main pot sized on it, the rest going to side pots.
+        let sb_pos = self.sb_pos();
+        let bb_pos = self.bb_pos();
+        if !self.dead_small_blind {
+            self.commit(sb_pos, self.small_blind.min(self.stacks[sb_pos]))?;
+            self.hand_actions.push((sb_pos, "small_blind".to_string(), self.bets[sb_pos]));
+        }
+        self.commit(bb_pos, self.big_blind.min(self.stacks[bb_pos]))?;
+        self.hand_actions.push((bb_pos, "big_blind".to_string(), self.bets[bb_pos]));
+
+        // A big blind ante is dead money for the main pot, from what the blind leaves him
+        if self.ante > 0 && self.ante_mode == AnteMode::BigBlindAnte {
+            let ante = self.ante.min(self.stacks[bb_pos] - self.bets[bb_pos]);
+            self.stacks[bb_pos] -= ante;
+            self.contributions[bb_pos] += ante;
+            self.pot += ante;
+            self.dead_ante = ante;
+            if self.stacks[bb_pos] == self.bets[bb_pos] {
+                self.all_in[bb_pos] = true;
+            }
+            self.hand_actions.push((bb_pos, "ante".to_string(), ante));
+        }
+
+        // A raise is at least a big blind, even when the blinds posted are short
+        self.max_raise = self.big_blind;
+
+        // The player after the big blind straddles, with three players or more and while the
+        // straddle is above the big blind. Preflop, he has the option and the action starts after him.
+        self.straddled = false;
+        if let Some(straddle) = self.straddle.filter(|&straddle| n > 2 && straddle > self.big_blind) {
+            let straddler = (bb_pos + 1) % n;
+            self.commit(straddler, straddle.min(self.stacks[straddler]))?;
+            self.hand_actions.push((straddler, "straddle".to_string(), self.bets[straddler]));
+            self.straddled = true;
+            self.max_raise = straddle;
+            self.current_player = (straddler + 1) % n;
+        }
 
-        // Force blinds
-        let sb_pos = (self.dealer_pos + 1) % self.num_players;
-        let bb_pos = (self.dealer_pos + 2) % self.num_players;
-        self.apply_bet(sb_pos, self.small_blind.min(self.stacks[sb_pos]))?;
-        self.apply_bet(bb_pos, self.big_blind.min(self.stacks[bb_pos]))?;
+        // Start on the first player who has a decision to make
+        self.seek_actor()?;
 
-        self.max_raise = self.bets.iter().max().copied().unwrap_or(0);
+        if let Some(callback) = &self.on_hand_start {
+            Python::with_gil(|py| callback.call1(py, (self.hands_played, self.dealer_pos)))?;
+        }
 
         Ok(())
     }
 
-    /// Apply a bet for a player
-    pub fn apply_bet(&mut self, player: usize, amount: i32) -> PyResult<()> {
-        self.bets[player] = amount;
+    /// Move the bets of the street into the pot
+    pub fn collect_bets(&mut self) -> PyResult<()> {
+        for i in 0..self.num_players {
+            self.contributions[i] += self.bets[i];
+            self.stacks[i] -= self.bets[i];
+            self.pot += self.bets[i];
+            self.bets[i] = 0;
+            // Bets are per street, so no chips behind once collected is all in
+            if self.stacks[i] == 0 && !self.folded[i] {
+                self.all_in[i] = true;
+            }
+        }
+        Ok(())
+    }
+
+    /// Rig the deck used by the next `reset` instead of a shuffled one.
+    /// Cards are dealt with `pop()`, from the end of the list: the hole cards to each player
+    /// (seat 0 first, one player at a time), then the flop, the turn and the river, each one after
+    /// a burned card if `burn_cards` is set.
+    pub fn rig_next_deal(&mut self, cards: Vec<String>) -> PyResult<()> {
+        if sorted_cards(&cards) != self.sorted_deck {
+            return Err(PyErr::new::<pyo3::exceptions::PyValueError, _>(
+                format!("Error: the deck must be a permutation of the {} cards of the game", self.sorted_deck.len()),
+            ));
+        }
+        self.rigged_deck = Some(cards);
+        Ok(())
+    }
+
+    /// Rebuild the rest of the deck from a new one, less the hands, the board and the burned
+    /// cards, and shuffle it with the env rng. The hand goes on with it, and a deck rigged for the
+    /// next reset is kept.
+    pub fn reshuffle(&mut self) {
+        self.base_deck = new_deck(self.short_deck);
+        let used: Vec<String> = [self.player_cards.concat(), self.community_cards.clone(), self.burned.clone()].concat();
+        self.deck = without(&self.base_deck, &used);
+        self.deck.shuffle(&mut self.rng);
+    }
+
+    /// Deck order the next `reset` will deal from, in the same layout as `rig_next_deal`.
+    /// The order is drawn from the env rng and kept for that reset, so the game itself is left
+    /// untouched. To deal from an order shuffled outside, pass it to `rig_next_deal` instead.
+    pub fn sample_deck(&mut self) -> Vec<String> {
+        if let Some(deck) = &self.rigged_deck {
+            return deck.clone();
+        }
+        let mut deck = self.base_deck.clone();
+        deck.shuffle(&mut self.rng);
+        self.rigged_deck = Some(deck.clone());
+        deck
+    }
+
+    /// Reseed the rng used to shuffle the deck
+    pub fn seed(&mut self, seed: u64) {
+        self.rng = ChaCha12Rng::seed_from_u64(seed);
+    }
+
+    /// Put the rest of the deck in another order, the cards given being those it holds
+    #[setter]
+    pub fn set_deck(&mut self, deck: Vec<String>) -> PyResult<()> {
+        self.check_cards(&deck, &self.player_cards, &self.community_cards)?;
+        if sorted_cards(&deck) != sorted_cards(&self.deck) {
+            return Err(PyErr::new::<pyo3::exceptions::PyValueError, _>(
+                format!("Error: the deck must be a permutation of the {} cards left in it", self.deck.len()),
+            ));
+        }
+        self.deck = deck;
+        Ok(())
+    }
+
+    /// Replace the hole cards, one list by player. The cards given are taken out of the deck and
+    /// the cards replaced go back under it, to be dealt last.
+    #[setter]
+    pub fn set_player_cards(&mut self, player_cards: Vec<Vec<String>>) -> PyResult<()> {
+        if player_cards.len() != self.num_players {
+            return Err(PyErr::new::<pyo3::exceptions::PyValueError, _>(format!(
+                "Error: {} hands given for {} players",
+                player_cards.len(),
+                self.num_players
+            )));
+        }
+        let deck = without(&[self.player_cards.concat(), self.deck.clone()].concat(), &player_cards.concat());
+        self.check_cards(&deck, &player_cards, &self.community_cards)?;
+        self.deck = deck;
+        self.player_cards = player_cards;
+        Ok(())
+    }
+
+    /// Replace the board. The cards given are taken out of the deck and the cards replaced go
+    /// back under it, to be dealt last.
+    #[setter]
+    pub fn set_community_cards(&mut self, community_cards: Vec<String>) -> PyResult<()> {
+        let deck = without(&[self.community_cards.clone(), self.deck.clone()].concat(), &community_cards);
+        self.check_cards(&deck, &self.player_cards, &community_cards)?;
+        self.deck = deck;
+        self.community_cards = community_cards;
+        Ok(())
+    }
+
+    /// Start the hand from a situation given as a dict, for training on chosen spots. Only
+    /// "phase" and "stacks" are needed, the stacks holding the chips of each seat this street
+    /// bets included, as in `get_full_state`. The "community_cards" must be those the phase
+    /// shows, and the "player_cards" are dealt if not given. The "bets" of the street default to
+    /// 0, "folded" to nobody, and the "contributions" of the previous streets to the "pot"
+    /// shared out between the players in the hand, the odd chips to the first ones. The
+    /// "current_player", left of the "dealer_pos" by default, must have a decision to make.
+    /// The rest of the deck is shuffled again, and the hand goes on from there.
+    pub fn set_scenario(&mut self, state: &Bound<'_, PyDict>) -> PyResult<()> {
+        let invalid = |problem: String| PyErr::new::<pyo3::exceptions::PyValueError, _>(format!("Error: {}", problem));
+        let n = self.num_players;
+        let phase: Phase = state.get_item("phase")?.ok_or_else(|| invalid("the scenario needs a phase".to_string()))?.extract()?;
+        if !state.contains("stacks")? {
+            return Err(invalid("the scenario needs stacks".to_string()));
+        }
+        let seat_values = |key: &str, default: Vec<i32>| -> PyResult<Vec<i32>> {
+            let values = state.get_item(key)?.map_or(Ok(default), |values| values.extract())?;
+            if values.len() != n {
+                return Err(invalid(format!("{} {} given for {} players", values.len(), key, n)));
+            }
+            Ok(values)
+        };
+        let stacks = seat_values("stacks", Vec::new())?;
+        let bets = seat_values("bets", vec![0; n])?;
+        let folded: Vec<bool> = state.get_item("folded")?.map_or(Ok(vec![false; n]), |folded| folded.extract())?;
+        if folded.len() != n || folded.iter().filter(|&&folded| !folded).count() < 2 {
+            return Err(invalid("two players or more must be in the hand".to_string()));
+        }
+
+        // Cards the phase has shown
+        let street = match phase {
+            Phase::Preflop => 0,
+            Phase::Flop => 1,
+            Phase::Turn => 2,
+            Phase::River => 3,
+            _ => return Err(invalid("the scenario must be in a betting round".to_string())),
+        };
+        if street > self.board_schedule.len() {
+            return Err(invalid(format!("the board schedule has no {:?}", phase)));
+        }
+        let shown: usize = self.board_schedule[..street].iter().sum();
+        let board: Vec<String> = state.get_item("community_cards")?.map_or(Ok(Vec::new()), |cards| cards.extract())?;
+        if board.len() != shown {
+            return Err(invalid(format!("the {:?} shows {} cards, {} given", phase, shown, board.len())));
+        }
+
+        // What the previous streets put in the pot
+        let in_hand: Vec<usize> = (0..n).filter(|&i| !folded[i]).collect();
+        let contributions = match state.get_item("contributions")? {
+            Some(_) => seat_values("contributions", Vec::new())?,
+            None => {
+                let pot: i32 = state.get_item("pot")?.map_or(Ok(0), |pot| pot.extract())?;
+                let mut contributions = vec![0; n];
+                for (k, &i) in in_hand.iter().enumerate() {
+                    contributions[i] = pot / in_hand.len() as i32 + ((k as i32) < pot % in_hand.len() as i32) as i32;
+                }
+                contributions
+            }
+        };
+        if contributions.iter().any(|&chips| chips < 0) {
+            return Err(invalid("contributions can't be negative".to_string()));
+        }
+
+        let mut env = self.clone();
+        env.dealer_pos = state.get_item("dealer_pos")?.map_or(Ok(self.dealer_pos), |seat| seat.extract())?;
+        if env.dealer_pos >= n {
+            return Err(invalid(format!("the dealer must be one of the {} seats", n)));
+        }
+        env.current_phase = phase;
+        env.stacks = stacks;
+        env.bets = bets;
+        env.pot = contributions.iter().sum();
+        env.hand_start_stacks = (0..n).map(|i| env.stacks[i] + contributions[i]).collect();
+        env.contributions = contributions;
+        env.folded = folded;
+        env.all_in = (0..n).map(|i| !env.folded[i] && env.stacks[i] == env.bets[i]).collect();
+        env.rewards = vec![0; n];
+        env.voluntary = vec![false; n];
+        env.raised_preflop = vec![false; n];
+        env.may_raise = vec![true; n];
+        env.has_acted_this_round = vec![false; n];
+        env.shaping_potential = vec![0; n];
+        env.shown_hands.clear();
+        env.terminal_states.clear();
+        env.hand_actions.clear();
+        env.street_starts = vec![0; street];
+        env.raises_this_street = 0;
+        env.actions_this_street = 0;
+        env.num_raises_this_hand = 0;
+        env.last_aggressor = None;
+        env.max_raise = env.big_blind;
+        env.dead_ante = 0;
+        env.straddled = false;
+        env.dead_small_blind = false;
+        env.runout_from = None;
+        env.second_board.clear();
+        env.burned.clear();
+
+        // Deal the hands not given from a deck shuffled again, without the cards placed
+        let given: Option<Vec<Vec<String>>> = state.get_item("player_cards")?.map(|cards| cards.extract()).transpose()?;
+        let placed = [given.clone().unwrap_or_default().concat(), board.clone()].concat();
+        env.check_cards(&placed, &[], &[])?;
+        env.deck = without(&env.base_deck, &placed);
+        env.deck.shuffle(&mut env.rng);
+        env.player_cards = match given {
+            Some(hands) => hands,
+            None => (0..n).map(|_| env.deck.split_off(env.deck.len() - env.hole_cards)).collect(),
+        };
+        if env.player_cards.len() != n || env.player_cards.iter().any(|hand| hand.len() != env.hole_cards) {
+            return Err(invalid(format!("expected {} hands of {} cards", n, env.hole_cards)));
+        }
+        env.community_cards = board;
+
+        env.current_player = state.get_item("current_player")?.map_or(Ok((env.dealer_pos + 1) % n), |seat| seat.extract())?;
+        if env.current_player >= n || env.folded[env.current_player] || env.all_in[env.current_player] {
+            return Err(invalid("the current player must be in the hand with chips behind".to_string()));
+        }
+        env.check_invariants("the scenario")?;
+        *self = env;
+        Ok(())
+    }
+
+    /// Check the whole env at once and raise on the first inconsistency found: a vector of
+    /// the table without one entry for each seat, a card found twice or not of the game, a card
+    /// of the game missing from the deck, the hands, the boards and the burned cards while the
+    /// hand is played (players eliminated leave with theirs), a board that doesn't match the
+    /// phase, a current player who can't act while the hand is played, or one of the checks of
+    /// `debug_invariants`, which only hold until the hand is over.
+    pub fn validate(&self) -> PyResult<()> {
+        let invalid = |problem: String| Err(PyErr::new::<pyo3::exceptions::PyValueError, _>(format!("Error: {}", problem)));
+        let n = self.num_players;
+        let lengths = [
+            ("names", self.names.len()),
+            ("stacks", self.stacks.len()),
+            ("bets", self.bets.len()),
+            ("contributions", self.contributions.len()),
+            ("folded", self.folded.len()),
+            ("all_in", self.all_in.len()),
+            ("has_acted_this_round", self.has_acted_this_round.len()),
+            ("player_cards", self.player_cards.len()),
+            ("voluntary", self.voluntary.len()),
+            ("raised_preflop", self.raised_preflop.len()),
+            ("may_raise", self.may_raise.len()),
+        ];
+        for (field, len) in lengths {
+            if len != n {
+                return invalid(format!("{} has {} entries for {} players", field, len, n));
+            }
+        }
+
+        self.check_cards(&self.deck, &self.player_cards, &self.community_cards)?;
+        if !self.hand_over() {
+            let cards = [
+                self.deck.clone(),
+                self.player_cards.concat(),
+                self.community_cards.clone(),
+                self.second_board.clone(),
+                self.burned.clone(),
+            ]
+            .concat();
+            if sorted_cards(&cards) != self.sorted_deck {
+                return invalid(format!(
+                    "{} cards in play instead of the {} of the game, missing {:?}",
+                    cards.len(),
+                    self.sorted_deck.len(),
+                    without(&self.sorted_deck, &cards)
+                ));
+            }
+        }
+        for (name, hand) in self.names.iter().zip(&self.player_cards) {
+            if hand.len() != self.hole_cards {
+                return invalid(format!("{} holds {} cards instead of {}", name, hand.len(), self.hole_cards));
+            }
+        }
+
+        // Before the showdown the board has the cards of the streets dealt so far, at most the
+        // whole board after it since a hand folded out stops dealing
+        let board = self.community_cards.len();
+        let streets = match self.current_phase {
+            Phase::Preflop => 0,
+            Phase::Flop => 1,
+            Phase::Turn => 2,
+            Phase::River => 3,
+            Phase::Showdown => self.board_schedule.len(),
+        };
+        let expected: usize = self.board_schedule.iter().take(streets).sum();
+        let board_ok = match self.current_phase {
+            Phase::Showdown => board <= expected,
+            _ => board == expected,
+        };
+        if !board_ok {
+            return invalid(format!("{} community cards on the {}", board, self.current_phase.name()));
+        }
+
+        if n > 0 {
+            if self.current_player >= n {
+                return invalid(format!("the current player is seat {} of {}", self.current_player, n));
+            }
+            if !self.hand_over()
+                && !self.betting_closed()
+                && (self.folded[self.current_player] || self.all_in[self.current_player])
+            {
+                return invalid(format!("{} has the turn but can't act", self.names[self.current_player]));
+            }
+        }
+
+        match n > 0 && !self.hand_over() {
+            true => self.check_invariants("the last change"),
+            false => Ok(()),
+        }
+    }
+
+    /// Give the turn to a seat, which must be in the hand with chips behind
+    #[setter]
+    pub fn set_current_player(&mut self, seat: usize) -> PyResult<()> {
+        if seat >= self.num_players {
+            return Err(PyErr::new::<pyo3::exceptions::PyValueError, _>(
+                format!("Error: no player at seat {}", seat),
+            ));
+        }
+        if self.folded[seat] || self.all_in[seat] {
+            return Err(PyErr::new::<pyo3::exceptions::PyValueError, _>(
+                format!("Error: {} is folded or all in, he can't act", self.names[seat]),
+            ));
+        }
+        self.current_player = seat;
+        Ok(())
+    }
+
+    /// Pass the turn to the next player left of the current one who can still bet, whatever
+    /// the betting round, and return his seat
+    pub fn skip_to_next_actor(&mut self) -> PyResult<usize> {
+        let next = self
+            .next_active_player((self.current_player + 1) % self.num_players)
+            .filter(|&seat| seat != self.current_player)
+            .ok_or_else(|| PyErr::new::<pyo3::exceptions::PyValueError, _>("Error: nobody else can act"))?;
+        self.current_player = next;
+        Ok(next)
+    }
+
+    /// Add `additional` chips to the bet of a player on this street, taken from the chips he has
+    /// behind it
+    pub fn commit(&mut self, player: usize, additional: i32) -> PyResult<()> {
+        let behind = self.stacks[player] - self.bets[player];
+        if additional < 0 || additional > behind {
+            return Err(PyErr::new::<pyo3::exceptions::PyValueError, _>(format!(
+                "Error: {} can't add {} to his bet of {} with {} behind",
+                self.names[player], additional, self.bets[player], behind
+            )));
+        }
+        self.bets[player] += additional;
         if self.stacks[player] - self.bets[player] == 0 {
             self.all_in[player] = true;
         }
         Ok(())
     }
 
-    /// Return all available actions for the current player
-    pub fn get_available_actions(&mut self) -> PyResult<Vec<Py<PyTuple>>> {
-        let mut actions: Vec<Py<PyTuple>> = Vec::new();
-        let current_bet = self.bets[self.current_player];
-        let current_stack = self.stacks[self.current_player];
-        let max_bet = self.bets.iter().max().copied().unwrap_or(0);
-
-        // No action if all in
-        if self.all_in[self.current_player] {
-            return Ok(actions);
-        };
+    /// Bring the bet of a player on this street up to `amount`, which can't be less than what he
+    /// has already bet nor more than his stack. Kept for callers used to total bets, `commit`
+    /// takes the chips added.
+    pub fn apply_bet(&mut self, player: usize, amount: i32) -> PyResult<()> {
+        self.commit(player, amount - self.bets[player])
+    }
+
+    /// Return all available actions for the current player
+    pub fn get_available_actions(&mut self) -> PyResult<Vec<Py<PyTuple>>> {
+        Python::with_gil(|py| self.available_actions(py))
+    }
+
+    /// Actions a player could take with the bets as they are, in the form of
+    /// `get_available_actions`, the turn staying where it is. None if he is folded or all in.
+    pub fn available_actions_for(&self, player: usize) -> PyResult<Vec<Py<PyTuple>>> {
+        if player >= self.num_players {
+            return Err(PyErr::new::<pyo3::exceptions::PyValueError, _>(
+                format!("Error: no player at seat {}", player),
+            ));
+        }
+        if self.folded[player] {
+            return Ok(Vec::new());
+        }
+        Python::with_gil(|py| self.seat_actions(py, player))
+    }
+
+    /// Fixed limit raise increment: small bet preflop and on the flop, big bet on the turn and river
+    pub fn limit_bet_size(&self) -> i32 {
+        match self.current_phase {
+            Phase::Preflop | Phase::Flop => self.big_blind,
+            _ => self.big_blind * 2,
+        }
+    }
+
+    /// Share of his chips of the hand the current player has put in the pot, this street
+    /// included: 1.0 once he has nothing behind
+    pub fn committed_fraction(&self) -> f64 {
+        let seat = self.current_player;
+        let put_in = self.contributions[seat] + self.bets[seat];
+        let behind = self.stacks[seat] - self.bets[seat];
+        if behind <= 0 {
+            return 1.0;
+        }
+        put_in as f64 / (put_in + behind) as f64
+    }
+
+    /// Total amount of chips committed to the pot, current street included
+    pub fn pot_size(&self) -> i32 {
+        self.pot + self.bets.iter().sum::<i32>()
+    }
+
+    /// Raise amount of the current player for a bet of `fraction` of the pot once his call is made,
+    /// e.g. 0.66 for two thirds of the pot, clamped to the raise range of `get_available_actions`
+    pub fn pot_fraction_to_amount(&self, fraction: f64) -> PyResult<i32> {
+        if fraction.is_nan() || fraction < 0.0 {
+            return Err(PyErr::new::<pyo3::exceptions::PyValueError, _>(
+                "Error: the pot fraction must be positive",
+            ));
+        }
+
+        let (min, max) = self.legal_actions()
+            .into_iter()
+            .find_map(|action| match action {
+                LegalAction::Raise(min, max) => Some((min, max)),
+                _ => None,
+            })
+            .ok_or_else(|| PyErr::new::<pyo3::exceptions::PyValueError, _>("Error: no raise is available"))?;
+
+        let max_bet = self.bets.iter().max().copied().unwrap_or(0);
+        let to_call = max_bet - self.bets[self.current_player];
+        let bet = (fraction * (self.pot_size() + to_call) as f64).round() as i32;
+        Ok((max_bet + bet).clamp(min, max))
+    }
+
+    /// Category of the current player's best hand with the board, e.g. "TwoPair", the best five
+    /// cards out of those known on the flop and the turn. "Incomplete" before the flop, or with a
+    /// board of less than three cards set by hand.
+    pub fn current_hand_category(&mut self) -> PyResult<String> {
+        if self.community_cards.len() < 3 {
+            return Ok("Incomplete".to_string());
+        }
+        let rank = self.rank_player(self.current_player, &self.community_cards)?;
+        Ok(rank_category(&rank).to_string())
+    }
+
+    /// Key of the information set of the current player, the same for the same situation:
+    /// "position|hole|board|actions", the hole cards and the flop sorted from the highest card.
+    /// Actions skip the forced bets and the streets are separated by "/", e.g.
+    /// "0|AsKh|Qd7c2h|c20r60c60/k" (f fold, k check, c call, r raise, a all in, with the amount).
+    pub fn infoset_key(&mut self) -> PyResult<String> {
+        let sorted = |cards: &[String]| -> PyResult<String> {
+            let mut indices = cards.iter().map(|card| card_to_index(card.clone())).collect::<PyResult<Vec<_>>>()?;
+            indices.sort_by_key(|&i| Reverse(i));
+            Ok(indices.into_iter().map(index_to_card_string).collect())
+        };
+
+        let hole = sorted(&self.player_cards[self.current_player])?;
+        let flop = self.community_cards.len().min(3);
+        let board = sorted(&self.community_cards[..flop])? + &self.community_cards[flop..].concat();
+
+        let mut actions = String::new();
+        for (i, (_, action, amount)) in self.hand_actions.iter().enumerate() {
+            actions.push_str(&"/".repeat(self.street_starts.iter().filter(|&&start| start == i).count()));
+            let code = match action.as_str() {
+                "fold" => "f".to_string(),
+                "check" => "k".to_string(),
+                "call" => format!("c{}", amount),
+                "raise" => format!("r{}", amount),
+                "allin" => format!("a{}", amount),
+                _ => String::new(),
+            };
+            actions.push_str(&code);
+        }
+        let pending = self.street_starts.iter().filter(|&&start| start == self.hand_actions.len()).count();
+        actions.push_str(&"/".repeat(pending));
+
+        Ok(format!("{}|{}|{}|{}", self.position(self.current_player), hole, board, actions))
+    }
+
+    /// Return observable state of game from the POV of the current player
+    pub fn get_state(&mut self) -> PyResult<Py<PyDict>> {
+        Python::with_gil(|py| self.state_dict(py))
+    }
+
+    /// Omniscient view of the table, every hand included, for training only (a centralized
+    /// critic for instance): never give it to an acting agent. Cards follow the encoding,
+    /// "player_cards" holding the hand of each seat, and stacks are always shown.
+    pub fn get_full_state(&mut self) -> PyResult<Py<PyDict>> {
+        Python::with_gil(|py| {
+            let dict = PyDict::new_bound(py);
+            match self.encoding {
+                Encoding::Cards => {
+                    dict.set_item("player_cards", self.player_cards.clone())?;
+                    dict.set_item("community_cards", self.community_cards.clone())?;
+                    dict.set_item("public_cards", self.public_cards())?;
+                }
+                Encoding::OneHot => {
+                    let hands = self.player_cards.iter().map(|hand| one_hot(hand)).collect::<PyResult<Vec<_>>>()?;
+                    dict.set_item("player_cards", hands)?;
+                    dict.set_item("community_cards", one_hot(&self.community_cards)?)?;
+                    let public = self.public_cards().iter().map(|cards| one_hot(cards)).collect::<PyResult<Vec<_>>>()?;
+                    dict.set_item("public_cards", public)?;
+                }
+            }
+            dict.set_item("names", self.names.clone())?;
+            dict.set_item("stacks", self.stacks.clone())?;
+            dict.set_item("bets", self.bets.clone())?;
+            dict.set_item("contributions", self.contributions.clone())?;
+            dict.set_item("pot", self.pot)?;
+            dict.set_item("total_pot", self.pot_size())?;
+            dict.set_item("num_players", self.num_players)?;
+            dict.set_item("phase", &self.current_phase)?;
+            dict.set_item("current_player", self.current_player)?;
+            dict.set_item("dealer_pos", self.dealer_pos)?;
+            dict.set_item("folded", self.folded.clone())?;
+            dict.set_item("all_in", self.all_in.clone())?;
+            dict.set_item("history", self.hand_actions.clone())?;
+            Ok(dict.into())
+        })
+    }
+
+    /// What the board allows, from the current player's view: the "nut_hand" any two cards he
+    /// can't see would make with it and the "nut_cards" making it, then the "outs", the cards
+    /// he can't see that would improve the category of his hand on the next card dealt, and
+    /// their number in "num_outs". Empty preflop, and no outs once the board is complete.
+    pub fn board_analysis(&mut self) -> PyResult<Py<PyDict>> {
+        Python::with_gil(|py| {
+            let dict = PyDict::new_bound(py);
+            if self.community_cards.len() < 3 {
+                return Ok(dict.into());
+            }
+            let hole = &self.player_cards[self.current_player];
+            let unseen = without(&self.base_deck, &self.visible_cards());
+
+            let mut nuts: Option<(u64, Rank, Vec<String>)> = None;
+            for cards in combinations(&unseen, 2) {
+                let rank = self.rank_hand(&cards, &self.community_cards)?;
+                let score = rank_score(&rank, self.short_deck);
+                if nuts.as_ref().is_none_or(|(best, _, _)| score > *best) {
+                    nuts = Some((score, rank, cards));
+                }
+            }
+            if let Some((_, rank, cards)) = nuts {
+                dict.set_item("nut_hand", describe_rank(&rank))?;
+                dict.set_item("nut_cards", cards)?;
+            }
+
+            // Outs are only counted while a card is still to come
+            let mut outs = Vec::new();
+            if self.community_cards.len() < self.board_size() {
+                // The category is in the high bits of the score, in the order of the variant
+                let category = |rank: &Rank| rank_score(rank, self.short_deck) >> 32;
+                let made = category(&self.rank_hand(hole, &self.community_cards)?);
+                for card in &unseen {
+                    let board = [&self.community_cards[..], std::slice::from_ref(card)].concat();
+                    if category(&self.rank_hand(hole, &board)?) > made {
+                        outs.push(card.clone());
+                    }
+                }
+            }
+            dict.set_item("num_outs", outs.len())?;
+            dict.set_item("outs", outs)?;
+            Ok(dict.into())
+        })
+    }
+
+    /// Number of cards left in the deck, without their order
+    pub fn deck_remaining(&self) -> usize {
+        self.deck.len()
+    }
+
+    /// Cards the current player can see: his hole cards, the board, then the face up cards of
+    /// the others
+    pub fn visible_cards(&self) -> Vec<String> {
+        let mut cards = [&self.player_cards[self.current_player][..], &self.community_cards[..]].concat();
+        for (seat, public) in self.public_cards().into_iter().enumerate() {
+            if seat != self.current_player {
+                cards.extend(public);
+            }
+        }
+        cards
+    }
+
+    /// Indices (see `card_to_index`) of the current player's hole cards and of the community cards
+    pub fn get_card_indices(&self) -> PyResult<(Vec<usize>, Vec<usize>)> {
+        let hole = self.player_cards[self.current_player]
+            .iter()
+            .map(|card| card_to_index(card.clone()))
+            .collect::<PyResult<Vec<_>>>()?;
+        let board = self.community_cards
+            .iter()
+            .map(|card| card_to_index(card.clone()))
+            .collect::<PyResult<Vec<_>>>()?;
+        Ok((hole, board))
+    }
+
+    /// Observation of the current player as a flat vector for neural networks: the one hot hole
+    /// cards and board (52 each), the chips behind and the bets of each seat from his own on,
+    /// the total pot, these divided by the initial stack, his position one hot, and the action
+    /// mask. Eliminated seats are padded with zeros so the length stays `observation_size`.
+    pub fn get_observation_vector(&self) -> PyResult<Vec<f32>> {
+        let seats = self.observation_seats();
+        let scale = self.initial_stack as f32;
+        let mut obs = one_hot(&self.player_cards[self.current_player])?;
+        obs.extend(one_hot(&self.community_cards)?);
+
+        let mut behind = vec![0.0; seats];
+        let mut bets = vec![0.0; seats];
+        for k in 0..self.num_players {
+            let seat = (self.current_player + k) % self.num_players;
+            behind[k] = self.visible_stack(seat).map_or(0, |stack| stack - self.bets[seat]) as f32 / scale;
+            bets[k] = self.bets[seat] as f32 / scale;
+        }
+        obs.extend(behind);
+        obs.extend(bets);
+        obs.push(self.pot_size() as f32 / scale);
+
+        let mut position = vec![0.0; seats];
+        position[self.position(self.current_player)] = 1.0;
+        obs.extend(position);
+
+        obs.extend(self.action_mask().into_iter().map(|legal| legal as u8 as f32));
+        Ok(obs)
+    }
+
+    /// Length of `get_observation_vector`, constant for the table
+    pub fn observation_size(&self) -> usize {
+        2 * 52 + 3 * self.observation_seats() + 1 + 5
+    }
+
+    /// Probability for each player still in the hand to win it with the hole cards dealt, over
+    /// the runouts of the board from the cards nobody holds: all of them when there are at most
+    /// `samples`, `samples` random ones otherwise. Split pots count as a fraction of a win.
+    #[pyo3(signature = (samples=10000))]
+    pub fn showdown_equities(&mut self, samples: usize) -> PyResult<Py<PyDict>> {
+        let used: Vec<String> = [self.player_cards.concat(), self.community_cards.clone(), self.burned.clone()].concat();
+        let mut rng = self.rng.clone();
+        let runouts = self.runout_scores(&self.community_cards, &used, samples, &mut rng);
+        self.rng = rng;
+        let runouts = runouts?;
+
+        let mut wins: BTreeMap<String, f64> = BTreeMap::new();
+        for scores in &runouts {
+            let best = scores.first().map(|&(_, score)| score);
+            let winners: Vec<&String> = scores.iter().filter(|(_, score)| Some(*score) == best).map(|(name, _)| name).collect();
+            for name in &winners {
+                *wins.entry(name.to_string()).or_insert(0.0) += 1.0 / winners.len() as f64;
+            }
+        }
+
+        Python::with_gil(|py| {
+            let dict = PyDict::new_bound(py);
+            for seat in (0..self.num_players).filter(|&i| !self.folded[i]) {
+                let name = &self.names[seat];
+                let won = wins.get(name).copied().unwrap_or(0.0);
+                dict.set_item(name, won / runouts.len().max(1) as f64)?;
+            }
+            Ok(dict.into())
+        })
+    }
+
+    /// Overall state, all cards included, printed unless `print` is false
+    #[pyo3(signature = (print=true))]
+    pub fn overall_state(&mut self, print: bool) -> PyResult<String> {
+        let state = format!("phase: {0:?}\nplayers_cards: {1:?}\ncommunity_cards: {2:?}\nfolded: {3:?}\nall_in: {4:?}\nstacks: {5:?}\nbets: {6:?}\npot: {7}\n",
+                    self.current_phase,
+                    self.player_cards,
+                    self.community_cards,
+                    self.folded,
+                    self.all_in,
+                    self.stacks,
+                    self.bets,
+                    self.pot);
+        if print {
+            self.log(&Event::State { state: state.clone() })?;
+        }
+        Ok(state)
+    }
+
+    /// Table for people to read, cards drawn with suit symbols: the phase, the pot and the board,
+    /// then a line for each seat with the button "D", the turn marked by ">", the hole cards,
+    /// the stack and the bet. Folded players have their cards mucked as "--". With `hidden`,
+    /// only the cards of the current player are shown, the others as "??".
+    #[pyo3(signature = (hidden=false))]
+    pub fn render(&self, hidden: bool) -> String {
+        let cards = |cards: &[String]| cards.iter().map(|card| pretty_card(card)).collect::<Vec<_>>().join(" ");
+        let board = if self.community_cards.is_empty() { "-".to_string() } else { cards(&self.community_cards) };
+        let mut table = format!("{:<8} pot {:<6} board {}\n", self.current_phase.name(), self.pot_size(), board);
+        for i in 0..self.num_players {
+            let turn = if i == self.current_player && !self.hand_over() { ">" } else { " " };
+            let button = if i == self.dealer_pos { "D" } else { " " };
+            let hand = &self.player_cards[i];
+            let shown = if self.folded[i] {
+                vec!["--"; hand.len()].join(" ")
+            } else if hidden && i != self.current_player {
+                vec!["??"; hand.len()].join(" ")
+            } else {
+                cards(hand)
+            };
+            let status = if self.folded[i] {
+                "(folded)"
+            } else if self.all_in[i] {
+                "(all in)"
+            } else {
+                ""
+            };
+            let line = format!(
+                "{}{} {:<10} {:<12} stack {:>6}  bet {:>6}  {}",
+                turn, button, self.names[i], shown, self.stacks[i], self.bets[i], status,
+            );
+            table.push_str(line.trim_end());
+            table.push('\n');
+        }
+        table
+    }
+
+    pub fn __repr__(&self) -> String {
+        format!(
+            "PokerEnv(phase={:?}, pot={}, active_players={}, stacks={:?})",
+            self.current_phase,
+            self.pot_size(),
+            self.folded.iter().filter(|&&b| !b).count(),
+            self.stacks,
+        )
+    }
+
+    /// The board, then a line for each player with his stack, his bet and his status
+    pub fn __str__(&self) -> String {
+        let mut table = format!("{:?} | board: {} | pot: {}\n", self.current_phase, self.community_cards.join(" "), self.pot_size());
+        for i in 0..self.num_players {
+            let status = if self.folded[i] {
+                "folded"
+            } else if self.all_in[i] {
+                "all in"
+            } else {
+                ""
+            };
+            let marker = if i == self.dealer_pos { "D" } else { " " };
+            table.push_str(&format!(
+                "{} {:<10} stack {:>6}  bet {:>6}  {}\n",
+                marker, self.names[i], self.stacks[i], self.bets[i], status,
+            ));
+        }
+        table
+    }
+
+    /// Proceed 1 turn of bet
+    pub fn step_bid(&mut self, verbose: bool) -> PyResult<()> {
+        while self.seek_actor()? {
+            // Build the decision, ask the agent and read its answer at once. Native seats play
+            // in Rust, without building the observation.
+            let mut rng = self.rng.clone();
+            let decision = Python::with_gil(|py| {
+                let agent = self.agents[self.current_player].bind(py);
+                if let Ok(strategy) = agent.extract::<NativeStrategy>() {
+                    return strategy.choose(self, SHOVE_THRESHOLD, &mut rng);
+                }
+                if let Ok(native) = agent.extract::<NativeAgent>() {
+                    return native.strategy.choose(self, native.threshold, &mut rng);
+                }
+                let state = self.state_dict(py)?;
+                let available_actions = self.available_actions(py)?;
+                // An agent without the method is called directly, as a plain function
+                let action = if agent.hasattr(self.agent_method.as_str())? {
+                    agent.call_method1(self.agent_method.as_str(), (state, available_actions))?
+                } else {
+                    agent.call1((state, available_actions))?
+                };
+                extract_action(&action)
+            });
+            self.rng = rng;
+            let (action_type, amount) = decision?;
+            self.play_action(action_type, amount, verbose)?;
+
+            if self.end_of_turn() {
+                break;
+            }
+        }
+
+        Ok(())
+    }
+
+    /// `rewards` of the last hand in the units of `reward_scale`
+    #[getter]
+    pub fn scaled_rewards(&self) -> Vec<f64> {
+        self.rewards.iter().map(|&r| self.scale_reward(r)).collect()
+    }
+
+    /// Apply one action for the current player and play until the next decision.
+    /// Return the next observation, the reward of the acting player (with sparse rewards, 0 until
+    /// the hand is over, then his net chip delta), whether the hand is over and an info dict.
+    /// The reward is a float in the units of `reward_scale`, with the big blind of the current level.
+    /// Once the hand is over, the observation is the acting player's final view (the first
+    /// seat's if he has been eliminated) and `reset` must be called to deal a new hand.
+    /// If nobody has a decision to make (everyone is all in), the action is ignored. An action
+    /// that is not among the legal ones raises an error listing them, and nothing is played.
+    pub fn step(&mut self, action: Py<PyTuple>) -> PyResult<(Py<PyDict>, f64, bool, Py<PyDict>)> {
+        let (action_type, amount) = Python::with_gil(|py| {
+            extract_action(action.bind(py).as_any())
+        })?;
+        self.step_action(action_type, amount)
+    }
+
+    /// Whether the hand has been played to its end, and `reset` must deal the next one
+    pub fn is_hand_over(&self) -> bool {
+        self.hand_over()
+    }
+
+    /// How the hand ended, None while it is played: "walk" when everyone folded to the big
+    /// blind, "all_folded" when everyone else folded, "all_in_runout" when the board was dealt
+    /// out after the betting closed with someone all in, "showdown" otherwise
+    pub fn terminal_reason(&self) -> Option<String> {
+        if !self.hand_over() {
+            return None;
+        }
+        // Busted players are already gone after a showdown, so the phase is checked first
+        let reason = if self.current_phase != Phase::Showdown {
+            if self.is_walk() { "walk" } else { "all_folded" }
+        } else if self.runout_from.is_some_and(|from| from < self.board_size()) {
+            "all_in_runout"
+        } else {
+            "showdown"
+        };
+        Some(reason.to_string())
+    }
+
+    /// Play again a recorded hand: deal `deck` as `rig_next_deal` does, with the button at
+    /// `dealer_pos` if given, then apply the actions of its history ((seat, action, amount) as in
+    /// the "history" of `get_state`, forced bets skipped). Fail at the first action out of turn
+    /// or illegal. Return whether the hand is over.
+    #[pyo3(signature = (deck, actions, dealer_pos=None))]
+    pub fn replay(&mut self, deck: Vec<String>, actions: Vec<(usize, String, i32)>, dealer_pos: Option<usize>) -> PyResult<bool> {
+        self.rig_next_deal(deck)?;
+        if let Some(seat) = dealer_pos {
+            if seat >= self.num_players {
+                return Err(PyErr::new::<pyo3::exceptions::PyValueError, _>(
+                    format!("Error: the dealer must be one of the {} seats", self.num_players),
+                ));
+            }
+            // reset moves the button on by one seat
+            self.dealer_pos = if self.rotate_button { (seat + self.num_players - 1) % self.num_players } else { seat };
+        }
+        self.reset(false)?;
+
+        let mut done = self.hand_over();
+        let forced = ["ante", "small_blind", "big_blind", "straddle"];
+        for (i, (seat, name, amount)) in actions.into_iter().enumerate() {
+            if forced.contains(&name.as_str()) {
+                continue;
+            }
+            let action = [Action::Fold, Action::Check, Action::Call, Action::Raise, Action::AllIn]
+                .into_iter()
+                .find(|action| action.name() == name)
+                .ok_or_else(|| PyErr::new::<pyo3::exceptions::PyValueError, _>(
+                    format!("Error: unknown action {:?}", name),
+                ))?;
+            if done || seat != self.current_player || !self.is_legal(&action, amount) {
+                return Err(PyErr::new::<pyo3::exceptions::PyValueError, _>(format!(
+                    "Error: action {} ({}, {:?}, {}) can't be played, seat {} is to act",
+                    i, seat, name, amount, self.current_player
+                )));
+            }
+            done = self.advance(action, amount)?.2;
+        }
+        Ok(done)
+    }
+
+    /// Actions of the discrete abstraction legal for the current player. Raises whose amount
+    /// would be the same as a smaller one or as the all in are left out.
+    pub fn abstract_actions(&self) -> PyResult<Vec<AbstractAction>> {
+        let legal = self.legal_actions();
+        if legal.is_empty() {
+            return Ok(Vec::new());
+        }
+
+        let mut actions = vec![AbstractAction::Fold, AbstractAction::CheckCall];
+        let all_in = legal.iter().find_map(|action| match action {
+            LegalAction::AllIn(amount) => Some(*amount),
+            _ => None,
+        });
+        if legal.iter().any(|action| matches!(action, LegalAction::Raise(_, _))) {
+            let mut sizes = Vec::new();
+            for action in [AbstractAction::HalfPot, AbstractAction::Pot] {
+                let (_, amount) = self.concrete_action(&action)?;
+                if !sizes.contains(&amount) && Some(amount) != all_in {
+                    sizes.push(amount);
+                    actions.push(action);
+                }
+            }
+        }
+        if all_in.is_some() {
+            actions.push(AbstractAction::AllIn);
+        }
+        Ok(actions)
+    }
+
+    /// Concrete action and amount of an abstract one, for `step` or an agent's answer
+    pub fn concrete_action(&self, action: &AbstractAction) -> PyResult<(Action, i32)> {
+        let check = self.legal_actions().contains(&LegalAction::Check);
+        Ok(match action {
+            AbstractAction::Fold => (Action::Fold, 0),
+            AbstractAction::CheckCall if check => (Action::Check, 0),
+            AbstractAction::CheckCall => {
+                let amount = self.legal_actions()
+                    .into_iter()
+                    .find_map(|action| match action {
+                        LegalAction::Call(amount) => Some(amount),
+                        _ => None,
+                    })
+                    .unwrap_or(0);
+                (Action::Call, amount)
+            }
+            AbstractAction::HalfPot => (Action::Raise, self.pot_fraction_to_amount(0.5)?),
+            AbstractAction::Pot => (Action::Raise, self.pot_fraction_to_amount(1.0)?),
+            AbstractAction::AllIn => (Action::AllIn, self.stacks[self.current_player]),
+        })
+    }
+
+    /// `step` with an action of the discrete abstraction
+    pub fn step_abstract(&mut self, action: AbstractAction) -> PyResult<(Py<PyDict>, f64, bool, Py<PyDict>)> {
+        if self.hand_over() {
+            return Err(PyErr::new::<pyo3::exceptions::PyValueError, _>(
+                "Error: hand is over, reset the env",
+            ));
+        }
+        if !self.abstract_actions()?.contains(&action) {
+            return Err(PyErr::new::<pyo3::exceptions::PyValueError, _>(
+                format!("Error: {:?} is not available", action),
+            ));
+        }
+        let (action_type, amount) = self.concrete_action(&action)?;
+        self.step_action(action_type, amount)
+    }
+
+    /// Advance to the next phase of the game: deal the next street of the board schedule, or go
+    /// to the showdown after the last one.
+    /// `on_phase_change` is then called with the previous phase, the new one and the cards dealt.
+    pub fn advance_phase(&mut self, verbose: bool) -> PyResult<()> {
+        let street = match self.current_phase {
+            Phase::Preflop => 0,
+            Phase::Flop => 1,
+            Phase::Turn => 2,
+            Phase::River => 3,
+            _ => {
+                return Err(PyErr::new::<pyo3::exceptions::PyValueError, _>("Error of phase"));
+            }
+        };
+        match street {
+            0 if !self.board_schedule.is_empty() => self.deal_flop(verbose),
+            1 if self.board_schedule.len() > 1 => self.deal_turn(verbose),
+            2 if self.board_schedule.len() > 2 => self.deal_river(verbose),
+            _ => self.deal_street(street, verbose),
+        }
+    }
+
+    /// Close the preflop betting and deal the flop, the first street of the board schedule
+    pub fn deal_flop(&mut self, verbose: bool) -> PyResult<()> {
+        self.deal_in_order(Phase::Preflop, 0, verbose)
+    }
+
+    /// Close the flop betting and deal the turn, the second street of the board schedule
+    pub fn deal_turn(&mut self, verbose: bool) -> PyResult<()> {
+        self.deal_in_order(Phase::Flop, 1, verbose)
+    }
+
+    /// Close the turn betting and deal the river, the third street of the board schedule
+    pub fn deal_river(&mut self, verbose: bool) -> PyResult<()> {
+        self.deal_in_order(Phase::Turn, 2, verbose)
+    }
+
+    /// Deal the rest of the board at once, burning cards as each street would, and go to the
+    /// showdown. Only once the betting is over, everyone left but one being all in.
+    pub fn run_out_board(&mut self, verbose: bool) -> PyResult<()> {
+        if self.current_phase == Phase::Showdown {
+            return Ok(());
+        }
+        if !self.betting_closed() {
+            return Err(PyErr::new::<pyo3::exceptions::PyValueError, _>(
+                "Error: players can still bet, the board can't be run out",
+            ));
+        }
+        while self.current_phase != Phase::Showdown {
+            self.advance_phase(verbose)?;
+        }
+        Ok(())
+    }
+
+    /// Kill a player (when he has no stack left). He is added to the finishing order, followed
+    /// by the winner once a single player remains.
+    pub fn kill(&mut self, player: usize) -> PyResult<()> { 
+        let (agent, name) = self.remove_seat(player);
+        if let Some(agent) = agent {
+            self.dead_agents.push(agent);
+        }
+        self.finishing_order.push(name.clone());
+        self.dead_names.push(name);
+        if self.num_players == 1 {
+            self.finishing_order.push(self.names[0].clone());
+        }
+        Ok(())
+    }
+
+    /// Sit a new player down at the end of the table with `buyin` chips, between hands.
+    /// A hand dealt on which nobody has acted yet is called off, the blinds going back.
+    /// `reset` then deals the next hand with him. Return his seat.
+    pub fn seat_player(&mut self, agent: PyObject, name: String, buyin: i32) -> PyResult<usize> {
+        if buyin <= 0 {
+            return Err(PyErr::new::<pyo3::exceptions::PyValueError, _>(
+                "Error: the buy-in must be positive",
+            ));
+        }
+        if self.names.contains(&name) || self.dead_names.contains(&name) {
+            return Err(PyErr::new::<pyo3::exceptions::PyValueError, _>(
+                format!("Error: there is already a player named {}", name),
+            ));
+        }
+        let burns = if self.burn_cards { self.board_schedule.len() } else { 0 };
+        let max_players = (self.base_deck.len() - self.board_size() - burns) / self.hole_cards;
+        if self.num_players >= max_players {
+            return Err(PyErr::new::<pyo3::exceptions::PyValueError, _>(
+                format!("Error: the table is full with {} players", max_players),
+            ));
+        }
+        self.call_off_hand()?;
+
+        // He sits out the hand in progress, if any
+        if self.agents.len() == self.num_players {
+            self.agents.push(agent);
+        }
+        self.names.push(name);
+        self.stacks.push(buyin);
+        self.hand_start_stacks.push(buyin);
+        self.bets.push(0);
+        self.contributions.push(0);
+        self.folded.push(true);
+        self.all_in.push(false);
+        self.rewards.push(0);
+        self.voluntary.push(false);
+        self.raised_preflop.push(false);
+        self.may_raise.push(true);
+        self.has_acted_this_round.push(false);
+        self.shaping_potential.push(0);
+        self.player_cards.push(Vec::new());
+        self.num_players += 1;
+        Ok(self.num_players - 1)
+    }
+
+    /// Stand a player up between hands, the others keeping their seats in order and their
+    /// stacks. A hand dealt on which nobody has acted yet is called off, the blinds going back.
+    /// Return the stack he leaves with.
+    pub fn unseat_player(&mut self, index: usize) -> PyResult<i32> {
+        if index >= self.num_players {
+            return Err(PyErr::new::<pyo3::exceptions::PyValueError, _>(
+                format!("Error: no player at seat {}", index),
+            ));
+        }
+        if self.num_players <= 2 {
+            return Err(PyErr::new::<pyo3::exceptions::PyValueError, _>(
+                "Error: at least 2 players must stay at the table",
+            ));
+        }
+        self.call_off_hand()?;
+
+        let stack = self.stacks[index];
+        self.hand_start_stacks.remove(index);
+        self.rewards.remove(index);
+        self.shaping_potential.remove(index);
+        self.remove_seat(index);
+        Ok(stack)
+    }
+
+    /// Give back to the top contributor the part of his contribution nobody matched
+    pub fn return_uncalled_bet(&mut self, verbose: bool) -> PyResult<()> {
+        let contributions = self.live_contributions();
+        let top = match (0..self.num_players).max_by_key(|&i| contributions[i]) {
+            Some(top) => top,
+            None => return Ok(()),
+        };
+        let second = (0..self.num_players)
+            .filter(|&i| i != top)
+            .map(|i| contributions[i])
+            .max()
+            .unwrap_or(0);
+
+        let uncalled = contributions[top] - second;
+        if uncalled > 0 {
+            self.contributions[top] -= uncalled;
+            self.stacks[top] += uncalled;
+            self.pot -= uncalled;
+            let player = self.names[top].clone();
+            self.emit(Event::UncalledBet { player, amount: uncalled }, verbose)?;
+        }
+        Ok(())
+    }
+
+    /// Main pot and side pots of the chips put in so far, bets of the street included, each
+    /// with the players still in the hand who can win it. The big blind ante goes to the main pot.
+    pub fn compute_pots(&self) -> Vec<(i32, Vec<String>)> {
+        let mut pots = vec![0];
+        let mut pots_names: Vec<Vec<String>> = vec![vec![]];
+
+        let sum_all_in: usize = self.all_in.iter().map(|&b| b as usize).sum();
+        if sum_all_in == 0 {
+            for i in 0..self.num_players {
+                pots[0] += self.contributions[i] + self.bets[i];
+
+                if !self.folded[i] {
+                    pots_names[0].push(self.names[i].clone())
+                }
+            }
+        } else {
+            let mut pot_index = 0;
+            let mut contributions: Vec<i32> = self.live_contributions().iter().zip(&self.bets).map(|(c, b)| c + b).collect();
+            pots[0] += self.dead_ante;
+
+            loop {
+                let min = contributions.iter()
+                    .zip(self.folded.iter())
+                    .filter_map(|(&num, &flag)| {
+                        if num != 0 && !flag {
+                            Some(num)
+                        } else {
+                            None
+                        }
+                    })
+                    .min();
+
+                if let Some(val) = min {
+                    for (i, contribution) in contributions.iter_mut().enumerate() {
+                        let n = std::cmp::min(val, *contribution);
+                        if n != 0 {
+                            *contribution -= n;
+                            pots[pot_index] += n;
+
+                            if !self.folded[i] {
+                                pots_names[pot_index].push(self.names[i].clone());
+                            }
+                        }
+                    }
+                    pots.push(0);
+                    pots_names.push(Vec::new());
+                    pot_index += 1;
+                } else {
+                    break;
+                }
+            }
+        }
+
+        // The last layer is left empty
+        pots.into_iter().zip(pots_names).filter(|(pot, _)| *pot > 0).collect()
+    }
+
+    /// Determine winner(s) and conclude a game.
+    /// Return for each pot its index and its winners from the left of the dealer with the chips
+    /// each of them was given, the odd chips going one each to the first winners. A pot run
+    /// twice appears once for each board.
+    /// When two players or more reach the showdown, the hands shown are kept in `shown_hands`
+    /// and recorded in the history: every contender's by default, or with `muck_losers` only
+    /// those of the players winning a contested pot. Other hole cards are never revealed.
+    /// The net result of each player is kept in `rewards`, by seat of the start of the hand so
+    /// that players eliminated keep theirs, the results summing to minus the rake. A split pot
+    /// counts for each winner what he was really given, odd chip included. With
+    /// `allin_ev_rewards`, when the board was run out with the betting closed, it is what his
+    /// equity of each pot was worth at that point rather than what the runout gave him; the
+    /// stacks get the real pots either way.
+    /// Players eliminated get their final observation and net result in `terminal_states`,
+    /// also given to the `on_elimination` method of their agent if it has one.
+    /// Every agent seated for the hand, those eliminated before they leave included, is then given
+    /// its final observation, its net result and whether it was eliminated by its `on_hand_end`
+    /// method if it has one, walks and hands folded out included.
+    pub fn resolution(&mut self, verbose: bool) -> PyResult<Vec<PotAward>> {
+        self.collect_bets()?;
+        let stacks_before_resolution = self.stacks.iter().sum::<i32>() + self.pot;
+
+        // Each pot is shared between the boards: the one dealt, and a second one if run twice
+        let mut boards = vec![self.community_cards.clone()];
+        if let Some(second) = self.second_runout(verbose)? {
+            boards.push(second);
+        }
+        // A lone player left takes every pot, without ranking hands nor needing a board
+        let survivor = match self.folded_out() {
+            true => self.folded.iter().position(|&folded| !folded).map(|seat| self.names[seat].clone()),
+            false => None,
+        };
+        let scores = match &survivor {
+            Some(name) => vec![vec![(name.clone(), 0)]],
+            None => boards.iter().map(|board| self.showdown_scores(board)).collect::<PyResult<Vec<_>>>()?,
+        };
+
+        if self.is_walk() {
+            let player = self.names[self.bb_pos()].clone();
+            self.emit(Event::Walk { player }, verbose)?;
+        }
+
+        self.return_uncalled_bet(verbose)?;
+
+        let (pots, pots_names): (Vec<i32>, Vec<Vec<String>>) = self.compute_pots().into_iter().unzip();
+
+        self.emit(Event::Pots { pots: pots.clone(), players: pots_names.clone() }, verbose)?;
+
+        // Reveal the hands at showdown, the losers mucking theirs if asked
+        if !self.folded_out() {
+            for seat in 0..self.num_players {
+                if self.folded[seat] {
+                    continue;
+                }
+                let name = &self.names[seat];
+                let wins_contested = scores.iter().any(|run_scores| {
+                    pots.iter().zip(pots_names.iter()).any(|(&p, players)| {
+                        p > 0
+                            && players.len() > 1
+                            && players.contains(name)
+                            && run_scores
+                                .iter()
+                                .filter(|(n, _)| players.contains(n))
+                                .map(|(_, r)| *r)
+                                .max()
+                                == run_scores.iter().find(|(n, _)| n == name).map(|(_, r)| *r)
+                    })
+                });
+                if !self.muck_losers || wins_contested {
+                    let (player, cards) = (name.clone(), self.player_cards[seat].clone());
+                    self.shown_hands.push((player.clone(), cards.clone()));
+                    self.emit(Event::HandShown { player, cards }, verbose)?;
+                }
+            }
+        }
+
+        // Distribute the pots
+        let mut awards = Vec::new();
+        let mut rake = 0;
+        let mut won = vec![0; self.num_players];
+        let mut settled = Vec::new();
+        let mut i = 0;
+        for p in pots {
+
+            if p == 0 {
+                continue;
+            }
+
+            // The rake of the pot leaves the table, within what the cap of the hand leaves
+            let mut pot_rake = (p as f64 * self.rake_percent / 100.0).floor() as i32;
+            if let Some(cap) = self.rake_cap {
+                pot_rake = pot_rake.min(cap - rake).max(0);
+            }
+            rake += pot_rake;
+            let p = p - pot_rake;
+            settled.push((p, pots_names[i].clone()));
+
+            for (run, run_scores) in scores.iter().enumerate() {
+                // The odd chip of a pot run twice goes to the first board
+                let share = match (scores.len(), run) {
+                    (1, _) => p,
+                    (_, 0) => p - p / 2,
+                    _ => p / 2,
+                };
+
+                // Determine pot winner(s): every contender of the pot with its best rank, whatever
+                // the order of the scores
+                let eligible = || run_scores.iter().filter(|(name, _)| pots_names[i].contains(name));
+                let best = eligible().map(|(_, r)| *r).max();
+                let winners: Vec<String> = match &survivor {
+                    Some(name) => vec![name.clone()],
+                    None => eligible().filter(|(_, r)| Some(*r) == best).map(|(name, _)| name.clone()).collect(),
+                };
+
+                // Distribute gains, the odd chips going to the first winners left of the dealer, by
+                // seat alone
+                let mut odd_chips = share % (winners.len() as i32);
+                let takes = share / (winners.len() as i32);
+                let mut awarded = Vec::new();
+
+                for k in 1..=self.num_players {
+                    let j = (self.dealer_pos + k) % self.num_players;
+                    let agent_name = self.names[j].clone();
+                    if winners.contains(&agent_name) {
+                        let mut amount = takes;
+                        if odd_chips > 0 {
+                            amount += 1;
+                            odd_chips -= 1;
+                        }
+                        self.stacks[j] += amount;
+                        won[j] += amount;
+                        self.emit(Event::PotAwarded { pot: i, player: agent_name.clone(), amount }, verbose)?;
+                        awarded.push((agent_name, amount));
+                    }
+                }
+                awards.push((i, awarded));
+            }
+
+            i += 1;
+        }
+        self.pot = 0;
+        self.record_stats();
+
+        // The rewards are kept apart from the stacks, which always get the chips really won
+        let credited = match self.allin_ev_rewards {
+            true => self.allin_ev(&settled)?.unwrap_or_else(|| won.clone()),
+            false => won.clone(),
+        };
+        for j in 0..self.num_players {
+            self.rewards[j] = self.stacks[j] - won[j] + credited[j] - self.hand_start_stacks[j];
+        }
+
+        // Players busted in the same hand finish in the order of their stacks at its start,
+        // the shortest first
+        let mut busted: Vec<usize> = (0..self.num_players).filter(|&j| self.stacks[j] == 0).collect();
+        busted.sort_by_key(|&j| self.hand_start_stacks[j]);
+        // Before they leave the table, give them their last view and the chips they lost
+        if !self.agents.is_empty() {
+            let current_player = self.current_player;
+            for &j in &busted {
+                let reward = self.rewards[j];
+                self.current_player = j;
+                let state = Python::with_gil(|py| -> PyResult<Py<PyDict>> {
+                    let state = self.state_dict(py)?;
+                    let agent = self.agents[j].bind(py);
+                    if agent.hasattr("on_elimination")? {
+                        agent.call_method1("on_elimination", (state.clone_ref(py), reward))?;
+                    }
+                    Ok(state)
+                });
+                self.current_player = current_player;
+                self.terminal_states.push((self.names[j].clone(), reward, state?));
+            }
+            for j in 0..self.num_players {
+                let reward = self.rewards[j];
+                self.current_player = j;
+                let result = Python::with_gil(|py| -> PyResult<()> {
+                    let agent = self.agents[j].bind(py);
+                    if agent.hasattr("on_hand_end")? {
+                        let state = self.state_dict(py)?;
+                        agent.call_method1("on_hand_end", (state, reward, busted.contains(&j)))?;
+                    }
+                    Ok(())
+                });
+                self.current_player = current_player;
+                result?;
+            }
+        }
+        let busted: Vec<String> = busted.iter().map(|&j| self.names[j].clone()).collect();
+        let (seats, big_blind) = (self.names.clone(), self.bb_pos());
+        for agent_name in &busted {
+            if let Some(j) = self.names.iter().position(|n| n == agent_name) {
+                self.emit(Event::PlayerEliminated { player: agent_name.clone() }, verbose)?;
+                self.kill(j)?;
+            }
+        }
+        if !busted.is_empty() && self.rotate_button && self.num_players > 1 {
+            self.move_button_after_eliminations(&seats, big_blind);
+        }
+
+        self.emit(Event::Stacks { stacks: self.stacks.clone() }, verbose)?;
+
+        self.rake_collected += rake as i64;
+
+        let stacks_after_resolution = self.stacks.iter().sum::<i32>() + rake;
+        if stacks_after_resolution != stacks_before_resolution {
+            return Err(PyErr::new::<pyo3::exceptions::PyRuntimeError, _>(format!(
+                "Error: chips are not conserved, {} before resolution and {} after",
+                stacks_before_resolution, stacks_after_resolution
+            )));
+        }
+        let net = self.rewards.iter().sum::<i32>();
+        if net != -rake {
+            return Err(PyErr::new::<pyo3::exceptions::PyRuntimeError, _>(format!(
+                "Error: the rewards of the hand sum to {} with a rake of {}",
+                net, rake
+            )));
+        }
+
+        Ok(awards)
+    }
+
+    /// Serialize the whole game state and config to JSON, the rng state included and the agents
+    /// excepted
+    pub fn to_json(&self) -> PyResult<String> {
+        serde_json::to_string(self).map_err(|e| {
+            PyErr::new::<pyo3::exceptions::PyValueError, _>(format!("Error: can't serialize the env: {}", e))
+        })
+    }
+
+    /// Restore an env saved with `to_json`.
+    /// Agents can't be saved, their slots are filled with None and must be set again with `agents`.
+    #[staticmethod]
+    pub fn from_json(py: Python, s: String) -> PyResult<Self> {
+        let mut env: PokerEnv = serde_json::from_str(&s).map_err(|e| {
+            PyErr::new::<pyo3::exceptions::PyValueError, _>(format!("Error: can't deserialize the env: {}", e))
+        })?;
+        env.agents = vec![py.None(); env.names.len()];
+        env.dead_agents = vec![py.None(); env.dead_names.len()];
+        env.sorted_deck = sorted_cards(&env.base_deck);
+        Ok(env)
+    }
+
+    /// Pickle support, the state being the JSON of `to_json`. As with `from_json`, an unpickled
+    /// env has None agents to set again with `agents`, and no callbacks. It deals the next hands
+    /// from the rng where the original was.
+    pub fn __getstate__(&self) -> PyResult<String> {
+        self.to_json()
+    }
+
+    pub fn __setstate__(&mut self, py: Python, state: String) -> PyResult<()> {
+        *self = Self::from_json(py, state)?;
+        Ok(())
+    }
+
+    /// Arguments of the env that `__setstate__` then overwrites: a None agent for each seat, at
+    /// least two, with the blinds and the initial stack
+    pub fn __getnewargs__(&self, py: Python) -> (Vec<PyObject>, i32, i32, i32) {
+        (vec![py.None(); self.num_players.max(2)], self.small_blind, self.big_blind, self.initial_stack)
+    }
+
+    /// Copy of the env to explore another line of play: the game state, deck included, is
+    /// duplicated while the agents are shared with the original
+    pub fn clone_state(&self) -> PyResult<Self> {
+        let mut env = self.clone();
+        env.checkpoints.clear();
+        Ok(env)
+    }
+
+    /// Save the state of the hand being played, deck order included, and return the handle to
+    /// restore it with `restore_checkpoint`. The agents are not copied, only referred to.
+    pub fn save_checkpoint(&mut self) -> usize {
+        let checkpoint = self.checkpoint();
+        self.checkpoints.push(checkpoint);
+        self.checkpoints.len() - 1
+    }
+
+    /// Roll the hand back to a checkpoint, which stays available to explore another line.
+    /// Only the hand the checkpoint was saved in can be rolled back.
+    pub fn restore_checkpoint(&mut self, handle: usize) -> PyResult<()> {
+        let checkpoint = self.checkpoints.get(handle).cloned().ok_or_else(|| {
+            PyErr::new::<pyo3::exceptions::PyValueError, _>(format!("Error: no checkpoint {}", handle))
+        })?;
+        if checkpoint.hand_number != self.hand_number {
+            return Err(PyErr::new::<pyo3::exceptions::PyValueError, _>(format!(
+                "Error: checkpoint {} was saved in hand {}, not in hand {}",
+                handle, checkpoint.hand_number, self.hand_number
+            )));
+        }
+        self.rollback(checkpoint);
+        Ok(())
+    }
+
+    /// Drop the checkpoints from `handle` on, all of them by default
+    #[pyo3(signature = (handle=0))]
+    pub fn clear_checkpoints(&mut self, handle: usize) {
+        self.checkpoints.truncate(handle);
+    }
+
+    pub fn __deepcopy__(&self, _memo: &Bound<'_, PyDict>) -> PyResult<Self> {
+        self.clone_state()
+    }
+
+    /// Play `num_games` independent hands in parallel, every seat of the table starting with the
+    /// initial stack and playing its native strategy, without calling the agents. Each game is
+    /// dealt from its own seed, drawn from the env rng, so that a seeded env gives the same results.
+    /// Return a dict with the number of "games", and for each seat the total "rewards" (net chips),
+    /// the "mean_rewards" and the number of "wins" (hands with a positive net). "by_strategy" sums
+    /// them up for each strategy played: its "seats", the "hands" they played, the "chips_won",
+    /// the "wins" and the "win_rate" by hand.
+    pub fn simulate_batch(&mut self, py: Python, num_games: usize, strategies: Vec<NativeStrategy>) -> PyResult<Py<PyDict>> {
+        if strategies.len() != self.num_players || self.num_players < 2 {
+            return Err(PyErr::new::<pyo3::exceptions::PyValueError, _>(
+                format!("Error: expected one strategy for each of the {} players", self.num_players),
+            ));
+        }
+
+        let mut table = self.clone();
+        table.agents = Vec::new();
+        table.dead_agents = Vec::new();
+        // No Python is called from the workers, which run without the GIL
+        table.on_hand_start = None;
+        table.on_phase_change = None;
+        table.log_callback = None;
+        table.record_history = false;
+        table.history = Vec::new();
+        table.rigged_deck = None;
+        table.dead_small_blind_next = false;
+        table.stacks = self.names.iter().map(|name| self.starting_stack(name)).collect();
+
+        // Every game is played on its own env, nothing is shared between the workers but the table.
+        // Each one gets its own rng, seeded from the env rng and the game number.
+        let n = self.num_players;
+        let base_seed: u64 = self.rng.gen();
+        let stats = py.allow_threads(|| {
+            (0..num_games)
+                .into_par_iter()
+                .map(|game| {
+                    let mut env = table.clone();
+                    env.rng = ChaCha12Rng::seed_from_u64(base_seed ^ game as u64);
+                    env.dealer_pos = game % env.num_players;
+                    env.play_native_hand(&strategies).map(|rewards| GameResult { rewards })
+                })
+                .try_fold(|| BatchStats::new(n), |stats, result| result.map(|result| stats.add(result)))
+                .try_reduce(|| BatchStats::new(n), |a, b| Ok(a.merge(b)))
+        })?;
+
+        let mean_rewards: Vec<f64> = stats
+            .rewards
+            .iter()
+            .map(|&r| if stats.games > 0 { r as f64 / stats.games as f64 } else { 0.0 })
+            .collect();
+
+        let by_strategy = PyDict::new_bound(py);
+        let mut played: Vec<&NativeStrategy> = Vec::new();
+        for strategy in &strategies {
+            if !played.contains(&strategy) {
+                played.push(strategy);
+            }
+        }
+        for strategy in played {
+            let seats: Vec<usize> = (0..n).filter(|&seat| strategies[seat] == *strategy).collect();
+            let hands = seats.len() * stats.games;
+            let wins: usize = seats.iter().map(|&seat| stats.wins[seat]).sum();
+            let entry = PyDict::new_bound(py);
+            entry.set_item("seats", seats.clone())?;
+            entry.set_item("hands", hands)?;
+            entry.set_item("chips_won", seats.iter().map(|&seat| stats.rewards[seat]).sum::<i64>())?;
+            entry.set_item("wins", wins)?;
+            entry.set_item("win_rate", if hands > 0 { wins as f64 / hands as f64 } else { 0.0 })?;
+            by_strategy.set_item(strategy.name(), entry)?;
+        }
+
+        let dict = PyDict::new_bound(py);
+        dict.set_item("games", stats.games)?;
+        dict.set_item("rewards", stats.rewards)?;
+        dict.set_item("mean_rewards", mean_rewards)?;
+        dict.set_item("wins", stats.wins)?;
+        dict.set_item("by_strategy", by_strategy)?;
+        Ok(dict.into())
+    }
+
+    /// Return the recorded events as dicts, oldest first, each with the "hand" number it belongs to
+    pub fn get_history(&self) -> PyResult<Py<PyList>> {
+        Python::with_gil(|py| {
+            let events = self.history
+                .iter()
+                .map(|(hand, event)| {
+                    let dict = event.to_dict(py)?;
+                    dict.bind(py).set_item("hand", hand)?;
+                    Ok(dict)
+                })
+                .collect::<PyResult<Vec<_>>>()?;
+            Ok(PyList::new_bound(py, events).into())
+        })
+    }
+
+    /// Statistics of each player by name since the env was created: "hands" resolved, the
+    /// "vpip", "pfr" and "win_rate" as fractions of them and the "net_chips" won
+    pub fn get_stats(&self) -> PyResult<Py<PyDict>> {
+        Python::with_gil(|py| {
+            let dict = PyDict::new_bound(py);
+            for (name, stats) in &self.stats {
+                let rate = |count: u32| count as f64 / stats.hands.max(1) as f64;
+                let entry = PyDict::new_bound(py);
+                entry.set_item("hands", stats.hands)?;
+                entry.set_item("vpip", rate(stats.vpip))?;
+                entry.set_item("pfr", rate(stats.pfr))?;
+                entry.set_item("win_rate", rate(stats.won))?;
+                entry.set_item("net_chips", stats.net_chips)?;
+                dict.set_item(name, entry)?;
+            }
+            Ok(dict.into())
+        })
+    }
+
+    /// Forget the recorded events
+    pub fn clear_history(&mut self) -> PyResult<()> {
+        self.history.clear();
+        Ok(())
+    }
+
+    /// Revive all player to play another game, each one with his starting stack. The hands
+    /// played start over, not the hand numbers.
+    pub fn revive(&mut self) -> PyResult<()> {
+        for a in self.dead_agents.clone() {
+            self.agents.push(a);
+        };
+        self.dead_agents = Vec::new();
+        for n in self.dead_names.clone() {
+            self.names.push(n)
+        };
+        self.dead_names = Vec::new();
+        self.finishing_order = Vec::new();
+        self.num_players = self.agents.len();
+
+        self.stacks = self.names.iter().map(|name| self.starting_stack(name)).collect();
+        self.dealer_pos = 0;
+        self.dead_small_blind_next = false;
+        self.hands_played = 0;
+
+        self.reset(false)?;
+
+        Ok(())
+    }
+
+    /// play episode game(s) of poker, each one until a single player remains.
+    /// Return a summary of all of them: the "episodes" summaries in order, the total of
+    /// "hands_played", the episodes won by player name in "wins" and the "net_results" by player
+    /// name summed over the episodes. Each episode has the "final_stacks" by player name, the
+    /// names in "elimination_order", the "finishing_order" ending with the "champion", the
+    /// "net_results" of the episode against the starting stacks and the number of
+    /// "hands_played". With payouts, the "prizes" won by player name are added.
+    /// Players are named for good, so the results hold over the eliminations and revivals.
+    /// An episode may also stop before a hand once `max_hands` hands are played, or once a
+    /// player has `stop_on_target_stack` chips: its "stop_reason" is then "max_hands" or
+    /// "target_stack" instead of "last_player", and it has no champion nor prizes.
+    #[pyo3(signature = (episode, verbose, max_hands=None, stop_on_target_stack=None))]
+    pub fn play_game(
+        &mut self,
+        py: Python,
+        episode: i32,
+        verbose: bool,
+        max_hands: Option<i32>,
+        stop_on_target_stack: Option<i32>,
+    ) -> PyResult<Py<PyDict>> {
+        let mut summaries = Vec::new();
+        let mut total_hands = 0;
+        let wins = PyDict::new_bound(py);
+        let mut net_totals: BTreeMap<String, i32> = BTreeMap::new();
+
+        for i in 1..=episode {
+            if i % 1000 == 0 {
+                self.log(&Event::Progress { episode: i, episodes: episode })?;
+            }
+
+            let already_dead = self.dead_names.len();
+            let mut hands_played = 0;
+            let mut stop_reason = "last_player";
+
+            while self.num_players > 1 {
+                if max_hands.is_some_and(|max| hands_played >= max) {
+                    stop_reason = "max_hands";
+                    break;
+                }
+                if stop_on_target_stack.is_some_and(|target| self.stacks.iter().any(|&stack| stack >= target)) {
+                    stop_reason = "target_stack";
+                    break;
+                }
+                self.reset(false)?;
+                hands_played += 1;
+
+                loop {
+                    if verbose {
+                        self.overall_state(true)?;
+                    }
+
+                    self.step_bid(verbose)?;
+
+                    // Folded around: the last player wins without further cards being dealt
+                    if self.folded_out() {
+                        self.resolution(verbose)?;
+                        break;
+                    }
+
+                    // Nobody can bet anymore: straight to the showdown
+                    if self.betting_closed() {
+                        self.run_out_board(verbose)?;
+                    } else {
+                        self.advance_phase(verbose)?;
+                    }
+
+                    if self.current_phase == Phase::Showdown {
+                        if verbose {
+                            self.overall_state(true)?;
+                        }
+
+                        self.resolution(verbose)?;
+                        break;
+                    }
+                }
+            }
+
+            let summary = Python::with_gil(|py| -> PyResult<Py<PyDict>> {
+                let final_stacks = PyDict::new_bound(py);
+                let net_results = PyDict::new_bound(py);
+                let finals = self.names.iter().zip(self.stacks.iter().copied());
+                for (name, stack) in finals.chain(self.dead_names.iter().map(|name| (name, 0))) {
+                    final_stacks.set_item(name, stack)?;
+                    let net = stack - self.starting_stack(name);
+                    net_results.set_item(name, net)?;
+                    *net_totals.entry(name.clone()).or_insert(0) += net;
+                }
+
+                let dict = PyDict::new_bound(py);
+                dict.set_item("final_stacks", final_stacks)?;
+                dict.set_item("elimination_order", self.dead_names[already_dead..].to_vec())?;
+                dict.set_item("finishing_order", &self.finishing_order)?;
+                let champion = self.finishing_order.last().filter(|_| self.num_players == 1).cloned();
+                if let Some(name) = &champion {
+                    let won = wins.get_item(name)?.map_or(Ok(0), |count| count.extract::<i32>())?;
+                    wins.set_item(name, won + 1)?;
+                }
+                dict.set_item("champion", champion)?;
+                dict.set_item("net_results", net_results)?;
+                dict.set_item("stop_reason", stop_reason)?;
+                if let Some(payouts) = self.payouts.as_ref().filter(|_| self.num_players == 1) {
+                    // The prize pool is made of the buy-ins, the starting stack of each player
+                    let prize_pool: f64 =
+                        self.finishing_order.iter().map(|name| self.starting_stack(name) as f64).sum();
+                    let prizes = PyDict::new_bound(py);
+                    for (place, name) in self.finishing_order.iter().rev().enumerate() {
+                        prizes.set_item(name, payouts.get(place).unwrap_or(&0.0) * prize_pool)?;
+                    }
+                    dict.set_item("prizes", prizes)?;
+                }
+                dict.set_item("hands_played", hands_played)?;
+                Ok(dict.into())
+            })?;
+            summaries.push(summary);
+            total_hands += hands_played;
+
+            self.revive()?;
+        }
+
+        let dict = PyDict::new_bound(py);
+        dict.set_item("episodes", summaries)?;
+        dict.set_item("hands_played", total_hands)?;
+        dict.set_item("wins", wins)?;
+        dict.set_item("net_results", net_totals)?;
+        Ok(dict.into())
+    }
+}
+
+impl PokerEnv {
+    /// Check the options of a config and deal the first hand of the env it describes
+    fn build(agents: Vec<PyObject>, config: PokerConfig) -> PyResult<Self> {
+        let PokerConfig {
+            small_blind,
+            big_blind,
+            initial_stack,
+            betting_mode,
+            raise_cap,
+            hole_cards,
+            short_deck,
+            record_history,
+            encoding,
+            names,
+            blind_schedule,
+            run_it_twice,
+            max_raises_per_street,
+            dealer_pos,
+            rotate_button,
+            log_callback,
+            rake_percent,
+            rake_cap,
+            burn_cards,
+            agent_method,
+            payouts,
+            muck_losers,
+            on_hand_start,
+            on_phase_change,
+            board_schedule,
+            reward_mode,
+            equity_iterations,
+            auto_topup_to,
+            max_actions_per_round,
+            observation_level,
+            seed,
+            allin_ev_rewards,
+            ante_mode,
+            debug_invariants,
+            straddle,
+            stacks,
+            mark_allin_calls,
+            face_up_cards,
+            reward_scale,
+        } = config;
+        let num_players = agents.len();
+        let deck_size = if short_deck { 36 } else { 52 };
+        if hole_cards < 2 {
+            return Err(PyErr::new::<pyo3::exceptions::PyValueError, _>(
+                format!("Error: can't deal {} hole cards, at least 2 are needed", hole_cards),
+            ));
+        }
+        // The first hole cards of each hand may be dealt face up, as in stud
+        if face_up_cards > hole_cards {
+            return Err(PyErr::new::<pyo3::exceptions::PyValueError, _>(
+                format!("Error: can't deal {} of the {} hole cards face up", face_up_cards, hole_cards),
+            ));
+        }
+        if reward_scale == RewardScale::BigBlinds && big_blind <= 0 {
+            return Err(PyErr::new::<pyo3::exceptions::PyValueError, _>(
+                "Error: rewards in big blinds need a positive big blind",
+            ));
+        }
+        // Cards dealt on the flop, the turn and the river
+        let board_schedule = board_schedule.unwrap_or_else(|| vec![3, 1, 1]);
+        if board_schedule.is_empty()
+            || board_schedule.len() > 3
+            || board_schedule.contains(&0)
+            || board_schedule.iter().sum::<usize>() < 3
+        {
+            return Err(PyErr::new::<pyo3::exceptions::PyValueError, _>(
+                "Error: the board is dealt in 1 to 3 streets of at least a card, 3 cards or more in all",
+            ));
+        }
+
+        // The equity is only estimated for Hold'em
+        let holdem = hole_cards == 2 && !short_deck && board_schedule.iter().sum::<usize>() == 5;
+        if reward_mode == RewardMode::EquityShaped && (!holdem || equity_iterations == 0) {
+            return Err(PyErr::new::<pyo3::exceptions::PyValueError, _>(
+                "Error: equity shaped rewards need Hold'em and some equity iterations",
+            ));
+        }
+
+        // The deck must hold the hole cards of everyone and the cards of the board, and the
+        // burned cards if any, one a street
+        let board_size = board_schedule.iter().sum::<usize>() + if burn_cards { board_schedule.len() } else { 0 };
+        let max_players = (deck_size - board_size) / hole_cards;
+        if num_players < 2 || num_players > max_players {
+            return Err(PyErr::new::<pyo3::exceptions::PyValueError, _>(
+                format!(
+                    "Error: {} players at the table, a {}-card deck with {} hole cards allows 2 to {}",
+                    num_players, deck_size, hole_cards, max_players,
+                ),
+            ));
+        }
+
+        if dealer_pos.is_some_and(|seat| seat >= num_players) {
+            return Err(PyErr::new::<pyo3::exceptions::PyValueError, _>(
+                format!("Error: the dealer must be one of the {} seats", num_players),
+            ));
+        }
+
+        if straddle.is_some_and(|straddle| straddle <= big_blind) {
+            return Err(PyErr::new::<pyo3::exceptions::PyValueError, _>(
+                "Error: the straddle must be more than the big blind",
+            ));
+        }
+
+        if !(0.0..=100.0).contains(&rake_percent) || rake_cap.is_some_and(|cap| cap < 0) {
+            return Err(PyErr::new::<pyo3::exceptions::PyValueError, _>(
+                "Error: the rake must be a percentage from 0 to 100 with a positive cap",
+            ));
+        }
+
+        // Shares of the prize pool by place, the winner's first
+        if let Some(payouts) = &payouts {
+            if payouts.iter().any(|&share| share < 0.0) || payouts.iter().sum::<f64>() > 1.0 + 1e-9 {
+                return Err(PyErr::new::<pyo3::exceptions::PyValueError, _>(
+                    "Error: payouts must be positive shares of the prize pool, summing to at most 1",
+                ));
+            }
+        }
+
+        // Pots are shared out by name, so they must be unique
+        let names = names.unwrap_or_else(|| (0..num_players).map(default_name).collect());
+        let mut unique = names.clone();
+        unique.sort();
+        unique.dedup();
+        if names.len() != num_players || unique.len() != num_players {
+            return Err(PyErr::new::<pyo3::exceptions::PyValueError, _>(
+                format!("Error: expected {} unique names, one for each agent", num_players),
+            ));
+        }
+
+        // Each player may start with his own stack instead of the initial one
+        let stacks = stacks.unwrap_or_else(|| vec![initial_stack; num_players]);
+        if stacks.len() != num_players || stacks.iter().any(|&stack| stack <= 0) {
+            return Err(PyErr::new::<pyo3::exceptions::PyValueError, _>(
+                format!("Error: expected {} positive stacks, one for each agent", num_players),
+            ));
+        }
+
+        let initial_stacks = names.iter().cloned().zip(stacks.iter().copied()).collect();
+
+        let mut poker_env = PokerEnv {
+            agents: agents.clone(),
+            dead_agents: Vec::new(),
+            num_players: agents.len(),
+            names,
+            dead_names: Vec::new(),
+            finishing_order: Vec::new(),
+            small_blind,
+            big_blind,
+            ante: 0,
+            ante_mode,
+            dead_ante: 0,
+            straddle,
+            straddled: false,
+            blind_schedule: {
+                // The blinds given are the first level, until the schedule starts
+                let mut levels = blind_schedule.unwrap_or_default();
+                levels.sort_by_key(|level| level.0);
+                if levels.first().is_some_and(|level| level.0 > 0) {
+                    levels.insert(0, (0, small_blind, big_blind, 0));
+                }
+                levels
+            },
+            hands_played: 0,
+            hand_number: 0,
+            max_raise: 0,
+            initial_stack,
+            initial_stacks,
+            betting_mode,
+            raise_cap,
+            max_raises_per_street,
+            rake_percent,
+            rake_cap,
+            rake_collected: 0,
+            payouts,
+            muck_losers,
+            allin_ev_rewards,
+            debug_invariants,
+            mark_allin_calls,
+            shown_hands: Vec::new(),
+            terminal_states: Vec::new(),
+            raises_this_street: 0,
+            num_raises_this_hand: 0,
+            last_aggressor: None,
+            max_actions_per_round,
+            actions_this_street: 0,
+            hole_cards,
+            face_up_cards,
+            short_deck,
+            encoding,
+            observation_level,
+            stacks: stacks.clone(),
+            // The button moves on the first reset, so it starts one seat before the one asked
+            dealer_pos: match dealer_pos {
+                Some(seat) if rotate_button => (seat + num_players - 1) % num_players,
+                Some(seat) => seat,
+                None => 0,
+            },
+            rotate_button,
+            dead_small_blind: false,
+            dead_small_blind_next: false,
+            bets: vec![0; num_players],
+            pot: 0,
+            contributions: vec![0; num_players],
+            folded: vec![false; num_players],
+            all_in: vec![false; num_players],
+            rewards: vec![0; num_players],
+            current_phase: Phase::Preflop,
+            current_player: 0,
+            hand_start_stacks: stacks,
+            deck: Vec::new(),
+            base_deck: new_deck(short_deck),
+            sorted_deck: sorted_cards(&new_deck(short_deck)),
+            burn_cards,
+            burned: Vec::new(),
+            rigged_deck: None,
+            rng: match seed {
+                Some(seed) => ChaCha12Rng::seed_from_u64(seed),
+                None => ChaCha12Rng::from_entropy(),
+            },
+            player_cards: vec![Vec::new(); num_players],
+            community_cards: Vec::new(),
+            hand_actions: Vec::new(),
+            street_starts: Vec::new(),
+            voluntary: vec![false; num_players],
+            raised_preflop: vec![false; num_players],
+            may_raise: vec![true; num_players],
+            has_acted_this_round: vec![false; num_players],
+            stats: BTreeMap::new(),
+            run_it_twice,
+            runout_from: None,
+            second_board: Vec::new(),
+            agent_method,
+            log_callback,
+            on_hand_start,
+            on_phase_change,
+            board_schedule,
+            reward_mode,
+            reward_scale,
+            auto_topup_to,
+            chips_added: 0,
+            equity_iterations,
+            shaping_potential: vec![0; num_players],
+            checkpoints: Vec::new(),
+            record_history,
+            history: Vec::new(),
+        };
+
+        poker_env.reset(false)?;
+        Ok(poker_env)
+    }
+
+    /// Snapshot of the hand for `save_checkpoint`
+    fn checkpoint(&self) -> Checkpoint {
+        Checkpoint {
+            hand_number: self.hand_number,
+            history_len: self.history.len(),
+            agents: self.agents.clone(),
+            dead_agents: self.dead_agents.clone(),
+            names: self.names.clone(),
+            dead_names: self.dead_names.clone(),
+            finishing_order: self.finishing_order.clone(),
+            num_players: self.num_players,
+            dealer_pos: self.dealer_pos,
+            dead_small_blind_next: self.dead_small_blind_next,
+            hands_played: self.hands_played,
+            rake_collected: self.rake_collected,
+            stats: self.stats.clone(),
+            deck: self.deck.clone(),
+            burned: self.burned.clone(),
+            rng: self.rng.clone(),
+            player_cards: self.player_cards.clone(),
+            community_cards: self.community_cards.clone(),
+            second_board: self.second_board.clone(),
+            runout_from: self.runout_from,
+            stacks: self.stacks.clone(),
+            bets: self.bets.clone(),
+            pot: self.pot,
+            contributions: self.contributions.clone(),
+            folded: self.folded.clone(),
+            all_in: self.all_in.clone(),
+            has_acted_this_round: self.has_acted_this_round.clone(),
+            voluntary: self.voluntary.clone(),
+            raised_preflop: self.raised_preflop.clone(),
+            may_raise: self.may_raise.clone(),
+            rewards: self.rewards.clone(),
+            shaping_potential: self.shaping_potential.clone(),
+            shown_hands: self.shown_hands.clone(),
+            terminal_states: self.terminal_states.clone(),
+            current_phase: self.current_phase.clone(),
+            current_player: self.current_player,
+            max_raise: self.max_raise,
+            raises_this_street: self.raises_this_street,
+            num_raises_this_hand: self.num_raises_this_hand,
+            last_aggressor: self.last_aggressor,
+            actions_this_street: self.actions_this_street,
+            hand_actions: self.hand_actions.clone(),
+            street_starts: self.street_starts.clone(),
+        }
+    }
+
+    /// Put back the state of the hand saved in `checkpoint`
+    fn rollback(&mut self, checkpoint: Checkpoint) {
+        self.history.truncate(checkpoint.history_len);
+        self.agents = checkpoint.agents;
+        self.dead_agents = checkpoint.dead_agents;
+        self.names = checkpoint.names;
+        self.dead_names = checkpoint.dead_names;
+        self.finishing_order = checkpoint.finishing_order;
+        self.num_players = checkpoint.num_players;
+        self.dealer_pos = checkpoint.dealer_pos;
+        self.dead_small_blind_next = checkpoint.dead_small_blind_next;
+        self.hands_played = checkpoint.hands_played;
+        self.rake_collected = checkpoint.rake_collected;
+        self.stats = checkpoint.stats;
+        self.deck = checkpoint.deck;
+        self.burned = checkpoint.burned;
+        self.rng = checkpoint.rng;
+        self.player_cards = checkpoint.player_cards;
+        self.community_cards = checkpoint.community_cards;
+        self.second_board = checkpoint.second_board;
+        self.runout_from = checkpoint.runout_from;
+        self.stacks = checkpoint.stacks;
+        self.bets = checkpoint.bets;
+        self.pot = checkpoint.pot;
+        self.contributions = checkpoint.contributions;
+        self.folded = checkpoint.folded;
+        self.all_in = checkpoint.all_in;
+        self.has_acted_this_round = checkpoint.has_acted_this_round;
+        self.voluntary = checkpoint.voluntary;
+        self.raised_preflop = checkpoint.raised_preflop;
+        self.may_raise = checkpoint.may_raise;
+        self.rewards = checkpoint.rewards;
+        self.shaping_potential = checkpoint.shaping_potential;
+        self.shown_hands = checkpoint.shown_hands;
+        self.terminal_states = checkpoint.terminal_states;
+        self.current_phase = checkpoint.current_phase;
+        self.current_player = checkpoint.current_player;
+        self.max_raise = checkpoint.max_raise;
+        self.raises_this_street = checkpoint.raises_this_street;
+        self.num_raises_this_hand = checkpoint.num_raises_this_hand;
+        self.last_aggressor = checkpoint.last_aggressor;
+        self.actions_this_street = checkpoint.actions_this_street;
+        self.hand_actions = checkpoint.hand_actions;
+        self.street_starts = checkpoint.street_starts;
+    }
+
+    /// Deal a street of the board schedule, which must come right after the phase given
+    fn deal_in_order(&mut self, after: Phase, street: usize, verbose: bool) -> PyResult<()> {
+        let name = ["flop", "turn", "river"][street];
+        if self.current_phase != after {
+            return Err(PyErr::new::<pyo3::exceptions::PyValueError, _>(format!(
+                "Error: the {} is dealt after the {:?}, not in the {:?}",
+                name, after, self.current_phase
+            )));
+        }
+        if street >= self.board_schedule.len() {
+            return Err(PyErr::new::<pyo3::exceptions::PyValueError, _>(
+                format!("Error: the board schedule has no {}", name),
+            ));
+        }
+        self.deal_street(street, verbose)
+    }
+
+    /// Collect the bets and deal the street given, from the first player left of the button, or
+    /// go to the showdown when the board schedule has no such street
+    fn deal_street(&mut self, street: usize, verbose: bool) -> PyResult<()> {
+        let previous_phase = self.current_phase.clone();
+        self.raises_this_street = 0;
+        self.actions_this_street = 0;
+        self.may_raise.iter_mut().for_each(|may_raise| *may_raise = true);
+        self.has_acted_this_round.iter_mut().for_each(|acted| *acted = false);
+        self.collect_bets()?;
+        self.max_raise = self.big_blind;
+        let dealt = self.community_cards.len();
+        if self.runout_from.is_none() && self.betting_closed() {
+            self.runout_from = Some(dealt);
+        }
+
+        // The streets of the board schedule come in turn, then the showdown
+        match self.board_schedule.get(street) {
+            Some(&count) => {
+                self.current_player = self.postflop_first_pos();
+                self.burn()?;
+                for _ in 0..count {
+                    let card = self.deck.pop().ok_or_else(|| PyErr::new::<pyo3::exceptions::PyValueError, _>("Deck is empty"))?;
+                    self.community_cards.push(card);
+                }
+                self.current_phase = [Phase::Flop, Phase::Turn, Phase::River][street].clone();
+            }
+            None => {
+                self.current_phase = Phase::Showdown;
+            }
+        }
+        self.street_starts.push(self.hand_actions.len());
+        self.emit(Event::PhaseStart { phase: self.current_phase.clone() }, verbose)?;
+        let cards = self.community_cards[dealt..].to_vec();
+        if !cards.is_empty() {
+            self.emit(Event::BoardDealt { cards: cards.clone() }, verbose)?;
+        }
+
+        if let Some(callback) = &self.on_phase_change {
+            Python::with_gil(|py| callback.call1(py, (previous_phase, self.current_phase.clone(), cards)))?;
+        }
+
+        if self.debug_invariants {
+            self.check_invariants("the deal of the street")?;
+        }
+        Ok(())
+    }
+
+    /// Take a player out of every seat vector and move the button and the turn accordingly.
+    /// Return his agent, if the table has agents, and his name.
+    fn remove_seat(&mut self, player: usize) -> (Option<PyObject>, String) {
+        self.stacks.remove(player);
+        self.bets.remove(player);
+        self.contributions.remove(player);
+        // Native simulations run without agents
+        let agent = (player < self.agents.len()).then(|| self.agents.remove(player));
+        let name = self.names.remove(player);
+        self.folded.remove(player);
+        self.all_in.remove(player);
+        self.voluntary.remove(player);
+        self.raised_preflop.remove(player);
+        self.may_raise.remove(player);
+        self.has_acted_this_round.remove(player);
+        self.player_cards.remove(player);
+        self.num_players -= 1;
+
+        // Seats after the removed player move back by one. If he had the button, it goes back to
+        // the previous seat so that the next reset gives it to the player who was after him.
+        if self.num_players > 0 {
+            let remap = |seat: usize| {
+                if seat > player {
+                    seat - 1
+                } else if seat == player {
+                    (seat + self.num_players - 1) % self.num_players
+                } else {
+                    seat
+                }
+            };
+            self.dealer_pos = remap(self.dealer_pos);
+            self.current_player = remap(self.current_player);
+        }
+        self.last_aggressor = match self.last_aggressor {
+            Some(seat) if seat == player => None,
+            Some(seat) if seat > player => Some(seat - 1),
+            seat => seat,
+        };
+        (agent, name)
+    }
+
+    /// Call off the hand in progress before the table changes: refused once a player has acted,
+    /// otherwise the forced bets go back and the hand is not counted. Either way the hand is
+    /// left at the showdown, so that it stays over whoever sits down or stands up.
+    fn call_off_hand(&mut self) -> PyResult<()> {
+        if self.hand_over() {
+            self.current_phase = Phase::Showdown;
+            return Ok(());
+        }
+        let forced = ["ante", "small_blind", "big_blind", "straddle"];
+        if self.hand_actions.iter().any(|(_, action, _)| !forced.contains(&action.as_str())) {
+            return Err(PyErr::new::<pyo3::exceptions::PyValueError, _>(
+                "Error: players can only sit down or stand up between hands",
+            ));
+        }
+        for i in 0..self.num_players {
+            self.stacks[i] += self.contributions[i];
+            self.contributions[i] = 0;
+            self.bets[i] = 0;
+        }
+        self.pot = 0;
+        self.dead_ante = 0;
+        self.hands_played = self.hands_played.saturating_sub(1);
+        self.hand_number = self.hand_number.saturating_sub(1);
+        if self.rotate_button {
+            self.dealer_pos = (self.dealer_pos + self.num_players - 1) % self.num_players;
+            self.dead_small_blind_next = self.dead_small_blind;
+        }
+        self.current_phase = Phase::Showdown;
+        Ok(())
+    }
+
+    /// Body of `step`, once the action is read
+    fn step_action(&mut self, action_type: Action, amount: i32) -> PyResult<(Py<PyDict>, f64, bool, Py<PyDict>)> {
+        let (actor, reward, done) = self.advance(action_type, amount)?;
+        let reward = self.scale_reward(reward);
+
+        let obs = self.get_state()?;
+        let info = Python::with_gil(|py| -> PyResult<Py<PyDict>> {
+            let dict = PyDict::new_bound(py);
+            dict.set_item("phase", &self.current_phase)?;
+            dict.set_item("acting_player", actor)?;
+            Ok(dict.into())
+        })?;
+
+        Ok((obs, reward, done, info))
+    }
+
+    /// Apply the action and play until the next decision, without building the observation.
+    /// Return the acting player, his reward and whether the hand is over.
+    fn advance(&mut self, action_type: Action, amount: i32) -> PyResult<(usize, i32, bool)> {
+        if self.hand_over() {
+            return Err(PyErr::new::<pyo3::exceptions::PyValueError, _>(
+                "Error: hand is over, reset the env",
+            ));
+        }
+
+        let actor = self.current_player;
+        let name = self.names[actor].clone();
+
+        let done = if !self.seek_actor()? {
+            self.finish_round()?
+        } else {
+            self.play_action(action_type, amount, false)?;
+
+            if self.end_of_turn() || !self.seek_actor()? {
+                self.finish_round()?
+            } else {
+                false
+            }
+        };
+
+        // Rewards are kept by seat of the start of the hand, like the stacks it started with
+        let mut reward = 0;
+        if done {
+            let seat = self.names.iter().position(|n| *n == name);
+            reward = self.rewards[actor];
+            self.current_player = seat.unwrap_or(0);
+        }
+
+        // Shaped, the reward is the change of the expected chips, the final one being the net
+        if self.reward_mode == RewardMode::EquityShaped {
+            let potential = if done { reward } else { self.expected_net(actor)? };
+            reward = potential - self.shaping_potential[actor];
+            self.shaping_potential[actor] = potential;
+        }
+
+        Ok((actor, reward, done))
+    }
+
+    /// Scores of the players still in the hand for the runouts of `board` from the cards not
+    /// `used`: all of them when there are at most `samples`, `samples` random ones from `rng`
+    /// otherwise
+    fn runout_scores(&self, board: &[String], used: &[String], samples: usize, rng: &mut ChaCha12Rng) -> PyResult<Vec<Vec<(String, u64)>>> {
+        let mut remaining = without(&self.base_deck, used);
+        let missing = self.board_size().saturating_sub(board.len());
+
+        let count = (0..missing).fold(1.0, |acc, i| acc * (remaining.len() - i) as f64 / (i + 1) as f64);
+        let runouts = if count <= samples as f64 {
+            combinations(&remaining, missing)
+        } else {
+            (0..samples)
+                .map(|_| remaining.partial_shuffle(rng, missing).0.to_vec())
+                .collect()
+        };
+        runouts
+            .iter()
+            .map(|runout| self.showdown_scores(&[board, &runout[..]].concat()))
+            .collect()
+    }
+
+    /// Chips each seat would win from the `settled` pots at his equity when the betting closed,
+    /// None unless the board was run out from there. The total is that of the pots, the chips
+    /// left by the rounding going to the largest fractions, then to the first seats left of the
+    /// dealer.
+    fn allin_ev(&mut self, settled: &[(i32, Vec<String>)]) -> PyResult<Option<Vec<i32>>> {
+        let from = match self.runout_from {
+            Some(from) if from < self.board_size() && !self.folded_out() => from,
+            _ => return Ok(None),
+        };
+        // Only the cards everyone could see then are known, the burned ones were not
+        let board = &self.community_cards[..from];
+        let used = [self.player_cards.concat(), board.to_vec()].concat();
+        let mut rng = self.rng.clone();
+        let runouts = self.runout_scores(board, &used, ALLIN_EV_SAMPLES, &mut rng);
+        self.rng = rng;
+        let runouts = runouts?;
+        if runouts.is_empty() {
+            return Ok(None);
+        }
+
+        let mut shares = vec![0.0; self.num_players];
+        for (p, players) in settled {
+            for scores in &runouts {
+                let contenders: Vec<&(String, u64)> = scores.iter().filter(|(name, _)| players.contains(name)).collect();
+                let best = contenders.first().map(|&&(_, score)| score);
+                let winners: Vec<&String> = contenders.iter().filter(|(_, score)| Some(*score) == best).map(|(name, _)| name).collect();
+                for name in winners.iter() {
+                    if let Some(seat) = self.names.iter().position(|n| n == *name) {
+                        shares[seat] += *p as f64 / winners.len() as f64 / runouts.len() as f64;
+                    }
+                }
+            }
+        }
+
+        let mut credited: Vec<i32> = shares.iter().map(|share| share.floor() as i32).collect();
+        let total: i32 = settled.iter().map(|(p, _)| p).sum();
+        let mut seats: Vec<usize> = (1..=self.num_players).map(|k| (self.dealer_pos + k) % self.num_players).collect();
+        seats.sort_by(|&a, &b| (shares[b] - shares[b].floor()).total_cmp(&(shares[a] - shares[a].floor())));
+        let left = (total - credited.iter().sum::<i32>()).max(0) as usize;
+        for &seat in seats.iter().take(left) {
+            credited[seat] += 1;
+        }
+        Ok(Some(credited))
+    }
+
+    /// What a player would get back from the pot at his all in equity, less what he put in
+    fn expected_net(&mut self, player: usize) -> PyResult<i32> {
+        let invested = self.contributions[player] + self.bets[player];
+        if self.folded[player] {
+            return Ok(-invested);
+        }
+        let opponents = (0..self.num_players).filter(|&i| i != player && !self.folded[i]).count();
+        let share = equity(
+            self.player_cards[player].clone(),
+            self.community_cards.clone(),
+            opponents,
+            self.equity_iterations,
+            Some(self.rng.gen()),
+        )?;
+        Ok((share * self.pot_size() as f64).round() as i32 - invested)
+    }
+
+    /// Check that every card belongs to the game and is found only once among the deck, the
+    /// hands, the board and the burned cards
+    fn check_cards(&self, deck: &[String], player_cards: &[Vec<String>], community_cards: &[String]) -> PyResult<()> {
+        let mut seen = BTreeSet::new();
+        let cards = deck.iter().chain(player_cards.iter().flatten()).chain(community_cards).chain(&self.burned);
+        for card in cards {
+            if !self.base_deck.contains(card) {
+                return Err(PyErr::new::<pyo3::exceptions::PyValueError, _>(
+                    format!("Error: {:?} is not a card of the game", card),
+                ));
+            }
+            if !seen.insert(card) {
+                return Err(PyErr::new::<pyo3::exceptions::PyValueError, _>(
+                    format!("Error: duplicate card {:?}", card),
+                ));
+            }
+        }
+        Ok(())
+    }
+
+    /// Cards of the whole board
+    fn board_size(&self) -> usize {
+        self.board_schedule.iter().sum()
+    }
+
+    /// Seats at the start of the tournament, eliminated players included
+    fn observation_seats(&self) -> usize {
+        self.names.len() + self.dead_names.len()
+    }
+
+    /// What each player put in the pot, less the big blind ante which nobody has to match
+    fn live_contributions(&self) -> Vec<i32> {
+        let mut contributions = self.contributions.clone();
+        if self.dead_ante > 0 {
+            contributions[self.bb_pos()] -= self.dead_ante;
+        }
+        contributions
+    }
+
+    /// Seat of the small blind, the dealer himself heads-up
+    fn sb_pos(&self) -> usize {
+        if self.num_players == 2 {
+            self.dealer_pos
+        } else {
+            (self.dealer_pos + 1) % self.num_players
+        }
+    }
+
+    /// Seat of the big blind, the first player after the small blind, or after the dealer when
+    /// the small blind is dead. Heads-up, he is the non-dealer and so acts first after the flop.
+    fn bb_pos(&self) -> usize {
+        if self.dead_small_blind {
+            (self.dealer_pos + 1) % self.num_players
+        } else {
+            (self.sb_pos() + 1) % self.num_players
+        }
+    }
+
+    /// Place the button for the next hand once players of the hand `seats` have been eliminated,
+    /// `big_blind` being the seat of its big blind: the big blind moves to the next player left,
+    /// the last big blind posts the small one if he is still there, and the dealer is the player
+    /// before the small blind, or before the big blind when the small blind is dead.
+    fn move_button_after_eliminations(&mut self, seats: &[String], big_blind: usize) {
+        let n = self.num_players;
+        let seat_of = |name: &String| self.names.iter().position(|n| n == name);
+        let next_big_blind = match (1..seats.len()).find_map(|k| seat_of(&seats[(big_blind + k) % seats.len()])) {
+            Some(seat) => seat,
+            None => return,
+        };
+        let (dealer, dead_small_blind) = match seat_of(&seats[big_blind]) {
+            _ if n == 2 => ((next_big_blind + 1) % n, false),
+            Some(small_blind) => ((small_blind + n - 1) % n, false),
+            None => ((next_big_blind + n - 1) % n, true),
+        };
+        // The next reset moves the button by one
+        self.dealer_pos = (dealer + n - 1) % n;
+        self.dead_small_blind_next = dead_small_blind;
+    }
+
+    /// Seat of the first player to speak preflop, before a straddle: the player after the big
+    /// blind, under the gun from four players on, the dealer three-handed and heads-up
+    fn preflop_first_pos(&self) -> usize {
+        (self.bb_pos() + 1) % self.num_players
+    }
+
+    /// Seat of the first player to speak after the flop: the big blind heads-up, who is out of
+    /// position, otherwise the first player left of the button
+    fn postflop_first_pos(&self) -> usize {
+        if self.num_players == 2 {
+            self.bb_pos()
+        } else {
+            (self.dealer_pos + 1) % self.num_players
+        }
+    }
+
+    /// Seat relative to the dealer: 0 for the button, 1 for the small blind, 2 for the big blind...
+    /// Heads-up, the button is the small blind so the big blind is 1.
+    fn position(&self, seat: usize) -> usize {
+        (seat + self.num_players - self.dealer_pos) % self.num_players
+    }
+
+    /// Move `current_player` to the next player who has a decision to make.
+    /// Return false if the betting round is over.
+    fn seek_actor(&mut self) -> PyResult<bool> {
+        if self.folded_out() {
+            return Ok(false);
+        }
+        // Nobody left with chips to bet (all in or folded)
+        let next = match self.next_active_player(self.current_player) {
+            Some(next) => next,
+            None => return Ok(false),
+        };
+
+        if self.round_settled() {
+            return Ok(false);
+        }
+        self.current_player = next;
+
+        // Everyone else folded or is all in, and the bet is matched
+        Ok(self.legal_actions().len() > 1)
+    }
+
+    /// First seat from `from` on, itself included, of a player still in the hand with chips to
+    /// bet, None if there is none
+    fn next_active_player(&self, from: usize) -> Option<usize> {
+        (0..self.num_players)
+            .map(|k| (from + k) % self.num_players)
+            .find(|&seat| !self.folded[seat] && !self.all_in[seat])
+    }
+
+    /// Close the turn of the current player, and pass to the next one.
+    /// Return true if the betting round is over.
+    fn end_of_turn(&mut self) -> bool {
+        if self.folded_out() || self.round_settled() {
+            return true;
+        }
+
+        self.current_player = (self.current_player + 1) % self.num_players;
+        false
+    }
+
+    /// Whether the betting round is over: every player with chips behind has acted on this
+    /// street and matched the highest bet. Checks around end it after one turn each, a bet, a
+    /// check-raise or a re-raise gives a new turn to everyone who has to match it, and the big
+    /// blind, or the straddler, keeps his option preflop since the blinds are not actions.
+    fn round_settled(&self) -> bool {
+        let max_bet = self.bets.iter().max().copied().unwrap_or(0);
+        (0..self.num_players)
+            .filter(|&seat| !self.folded[seat] && !self.all_in[seat])
+            .all(|seat| self.has_acted_this_round[seat] && self.bets[seat] == max_bet)
+    }
+
+    /// Observable state of the game from the POV of the current player
+    fn state_dict(&self, py: Python) -> PyResult<Py<PyDict>> {
+        let dict = PyDict::new_bound(py);
+        match self.encoding {
+            Encoding::Cards => {
+                dict.set_item("player_cards", self.player_cards[self.current_player].clone())?;
+                dict.set_item("community_cards", self.community_cards.clone())?;
+                dict.set_item("public_cards", self.public_cards())?;
+            }
+            Encoding::OneHot => {
+                dict.set_item("player_cards", one_hot(&self.player_cards[self.current_player])?)?;
+                dict.set_item("community_cards", one_hot(&self.community_cards)?)?;
+                let public = self.public_cards().iter().map(|cards| one_hot(cards)).collect::<PyResult<Vec<_>>>()?;
+                dict.set_item("public_cards", public)?;
+            }
+        }
+        let stacks: Vec<Option<i32>> = (0..self.num_players).map(|i| self.visible_stack(i)).collect();
+        dict.set_item("stacks", stacks)?;
+        dict.set_item("bets", self.bets.clone())?;
+        dict.set_item("pot", self.pot)?;
+        // "pot" only holds the previous streets, "total_pot" adds the bets of this one
+        dict.set_item("total_pot", self.pot_size())?;
+        dict.set_item("active_players", self.active_players())?;
+        dict.set_item("num_players", self.num_players)?;
+        dict.set_item("hand_number", self.hand_number)?;
+        dict.set_item("phase", &self.current_phase)?;
+        dict.set_item("current_player", self.current_player)?;
+        dict.set_item("position", self.position(self.current_player))?;
+        dict.set_item("is_dealer", self.current_player == self.dealer_pos)?;
+        dict.set_item("folded", self.folded.clone())?;
+        dict.set_item("all_in", self.all_in.clone())?;
+        // Who has acted on this street, a raise since then giving him a new decision all the same
+        dict.set_item("has_acted", self.has_acted_this_round.clone())?;
+        // Raises of the hand so far, the blinds not counted, and the last player to raise with his
+        // distance in seats to the left of the current player
+        dict.set_item("num_raises_this_hand", self.num_raises_this_hand)?;
+        dict.set_item("last_aggressor", self.last_aggressor)?;
+        let aggressor_position = self
+            .last_aggressor
+            .map(|seat| (seat + self.num_players - self.current_player) % self.num_players);
+        dict.set_item("aggressor_position", aggressor_position)?;
+        dict.set_item("action_mask", self.action_mask())?;
+        // What calling costs on top of the bet already made, and the raise totals allowed if any
+        let seat = self.current_player;
+        let max_bet = self.bets.iter().max().copied().unwrap_or(0);
+        let to_call = max_bet.min(self.stacks[seat]) - self.bets[seat];
+        dict.set_item("to_call", to_call)?;
+        // The same in big blinds of the current level, None without a big blind
+        let in_big_blinds = |chips: i32| (self.big_blind > 0).then(|| chips as f64 / self.big_blind as f64);
+        dict.set_item("to_call_bb", in_big_blinds(to_call))?;
+        dict.set_item("pot_bb", in_big_blinds(self.pot))?;
+        dict.set_item("total_pot_bb", in_big_blinds(self.pot_size()))?;
+        // Share of the pot after calling that the call costs, 0 with nothing to call
+        let pot_odds = match to_call {
+            0 => 0.0,
+            _ => to_call as f64 / (self.pot_size() + to_call) as f64,
+        };
+        dict.set_item("pot_odds", pot_odds)?;
+        // Chips behind the current player can lose against the deepest opponent (his own when
+        // the other stacks are hidden) over the pot, None if the pot is empty
+        let behind = |i: usize| self.stacks[i] - self.bets[i];
+        let effective = match self.observation_level {
+            ObservationLevel::Full => (0..self.num_players)
+                .filter(|&i| i != seat && !self.folded[i])
+                .map(behind)
+                .max()
+                .map_or(behind(seat), |deepest| deepest.min(behind(seat))),
+            ObservationLevel::CommittedOnly => behind(seat),
+        };
+        // By seat, the chips behind the current player can lose against that opponent: 0 against
+        // a folded one, his own chips behind at his seat, None when the other stacks are hidden
+        let effective_stacks: Vec<Option<i32>> = (0..self.num_players)
+            .map(|i| match (self.folded[i] && i != seat, &self.observation_level) {
+                (true, _) => Some(0),
+                (false, ObservationLevel::Full) => Some(behind(i).min(behind(seat))),
+                (false, ObservationLevel::CommittedOnly) => (i == seat).then(|| behind(seat)),
+            })
+            .collect();
+        dict.set_item("effective_stacks", effective_stacks)?;
+        dict.set_item("committed_fraction", self.committed_fraction())?;
+        dict.set_item("spr", (self.pot_size() > 0).then(|| effective as f64 / self.pot_size() as f64))?;
+        let raise = self.legal_actions().into_iter().find_map(|legal| match legal {
+            LegalAction::Raise(min, max) => Some((min, max)),
+            _ => None,
+        });
+        dict.set_item("min_raise", raise.map(|(min, _)| min))?;
+        dict.set_item("max_raise", raise.map(|(_, max)| max))?;
+        dict.set_item("history", self.hand_actions.clone())?;
+        Ok(dict.into())
+    }
+
+    /// Face up cards of each seat, the first `face_up_cards` of his hand, seen by everyone
+    fn public_cards(&self) -> Vec<Vec<String>> {
+        self.player_cards.iter().map(|hand| hand[..self.face_up_cards.min(hand.len())].to_vec()).collect()
+    }
+
+    /// Stack of a seat as the current player sees it, per the observation level
+    fn visible_stack(&self, seat: usize) -> Option<i32> {
+        match self.observation_level {
+            ObservationLevel::CommittedOnly if seat != self.current_player => None,
+            _ => Some(self.stacks[seat]),
+        }
+    }
+
+    /// Available actions of the current player as the tuples given to the agents
+    fn available_actions(&self, py: Python) -> PyResult<Vec<Py<PyTuple>>> {
+        self.seat_actions(py, self.current_player)
+    }
+
+    /// Available actions of a seat as the tuples given to the agents.
+    /// With `mark_allin_calls`, the call tuple has a third item telling whether calling puts
+    /// him all in.
+    fn seat_actions(&self, py: Python, seat: usize) -> PyResult<Vec<Py<PyTuple>>> {
+        Ok(self.legal_actions_for(seat)
+            .into_iter()
+            .map(|legal| match legal {
+                LegalAction::Fold => PyTuple::new_bound(py, [Action::Fold.to_object(py)]).into(),
+                LegalAction::Check => PyTuple::new_bound(py, [Action::Check.to_object(py)]).into(),
+                LegalAction::Call(amount) if self.mark_allin_calls => {
+                    let all_in = amount == self.stacks[seat];
+                    PyTuple::new_bound(py, [Action::Call.to_object(py), amount.to_object(py), all_in.to_object(py)])
+                        .into()
+                }
+                LegalAction::Call(amount) => {
+                    PyTuple::new_bound(py, [Action::Call.to_object(py), amount.to_object(py)]).into()
+                }
+                LegalAction::Raise(min, max) => {
+                    PyTuple::new_bound(py, [Action::Raise.to_object(py), (min, max).to_object(py)]).into()
+                }
+                LegalAction::AllIn(amount) => {
+                    PyTuple::new_bound(py, [Action::AllIn.to_object(py), amount.to_object(py)]).into()
+                }
+            })
+            .collect())
+    }
+
+    /// Whether the current player may play the action, with this amount for a call or a raise.
+    /// Going all in is also a call of the whole stack.
+    fn is_legal(&self, action: &Action, amount: i32) -> bool {
+        let stack = self.stacks[self.current_player];
+        self.legal_actions().iter().any(|legal| match (legal, action) {
+            (LegalAction::Fold, Action::Fold) | (LegalAction::Check, Action::Check) => true,
+            (LegalAction::Call(call), Action::Call) => *call == amount,
+            (LegalAction::Call(call), Action::AllIn) => *call == stack,
+            (LegalAction::Raise(min, max), Action::Raise) => (*min..=*max).contains(&amount),
+            (LegalAction::AllIn(_), Action::AllIn) => true,
+            _ => false,
+        })
+    }
+
+    /// Actions allowed to the current player, none if he is all in
+    fn legal_actions(&self) -> Vec<LegalAction> {
+        self.legal_actions_for(self.current_player)
+    }
+
+    /// Actions a seat would be allowed with the bets as they are, none if he is all in
+    fn legal_actions_for(&self, seat: usize) -> Vec<LegalAction> {
+        let mut actions = Vec::new();
+        let current_bet = self.bets[seat];
+        let current_stack = self.stacks[seat];
+        let max_bet = self.bets.iter().max().copied().unwrap_or(0);
+
+        // No action if all in
+        if self.all_in[seat] {
+            return actions;
+        };
+
+        // Always fold
+        actions.push(LegalAction::Fold);
+
+        let sum_all_in: usize = self.all_in.iter().map(|&b| b as usize).sum();
+        let sum_folded: usize = self.folded.iter().map(|&b| b as usize).sum();
+
+        if sum_all_in + sum_folded == self.folded.len() - 1 {
+            if current_bet != max_bet {
+                actions.push(LegalAction::Call(max_bet.min(current_stack)));
+            }
+            return actions;
+        };
+
+        // "Check" is the bet of the player is equal to the max_bet, "Call" if not
+        if current_bet == max_bet {
+            actions.push(LegalAction::Check);
+        } else {
+            actions.push(LegalAction::Call(max_bet.min(current_stack)));
+        };
+
+        if current_stack > max_bet {
+            let raise_range = match self.betting_mode {
+                // Fixed limit: a single raise size, and no raise at all once the street is capped
+                BettingMode::FixedLimit => {
+                    if self.raises_this_street < self.raise_cap {
+                        let amount = (max_bet + self.limit_bet_size()).min(current_stack);
+                        Some((amount, amount))
+                    } else {
+                        None
+                    }
+                }
+                _ => {
+                    let mut raise_range = if current_stack >= max_bet*2 {
+                        ((max_bet + self.max_raise).min(current_stack), current_stack)
+                    } else {
+                        (current_stack, current_stack)
+                    };
+
+                    // Pot limit: raise at most the size of the pot once the call is made
+                    if self.betting_mode == BettingMode::PotLimit {
+                        let pot_limit = max_bet + self.pot_size() + (max_bet - current_bet);
+                        raise_range.1 = raise_range.1.min(pot_limit);
+                        raise_range.0 = raise_range.0.min(raise_range.1);
+                    }
+                    Some(raise_range)
+                }
+            };
+
+            // In any betting mode, no more raise once the optional cap of the street is reached,
+            // nor for a player who acted when only a short all in came since. Going all in for
+            // a call stays possible.
+            let capped = self.max_raises_per_street.is_some_and(|cap| self.raises_this_street >= cap)
+                || !self.may_raise[seat];
+
+            if let (Some((min, max)), false) = (raise_range, capped) {
+                actions.push(LegalAction::Raise(min, max));
+            }
+        };
+
+        // All in whenever a call or a raise can commit the whole stack
+        let commits_stack = actions.iter().any(|action| match action {
+            LegalAction::Call(amount) => *amount == current_stack,
+            LegalAction::Raise(_, max) => *max == current_stack,
+            _ => false,
+        });
+        if commits_stack {
+            actions.push(LegalAction::AllIn(current_stack));
+        }
+
+        actions
+    }
+
+    /// Legal actions of the current player, in the order [fold, check, call, raise, allin]
+    fn action_mask(&self) -> Vec<bool> {
+        let mut mask = vec![false; 5];
+        for legal in self.legal_actions() {
+            let index = match legal {
+                LegalAction::Fold => 0,
+                LegalAction::Check => 1,
+                LegalAction::Call(_) => 2,
+                LegalAction::Raise(_, _) => 3,
+                LegalAction::AllIn(_) => 4,
+            };
+            mask[index] = true;
+        }
+        mask
+    }
+
+    /// Deal and play a whole hand with a native strategy for each seat.
+    /// Return the net chips won by each seat.
+    fn play_native_hand(&mut self, strategies: &[NativeStrategy]) -> PyResult<Vec<i32>> {
+        let names = self.names.clone();
+        self.reset(false)?;
+
+        loop {
+            while self.seek_actor()? {
+                let mut rng = self.rng.clone();
+                let decision = strategies[self.current_player].choose(self, SHOVE_THRESHOLD, &mut rng);
+                self.rng = rng;
+                let (action, amount) = decision?;
+                self.play_action(action, amount, false)?;
+                if self.end_of_turn() {
+                    break;
+                }
+            }
+
+            if self.folded_out() {
+                break;
+            }
+            self.advance_phase(false)?;
+            if self.current_phase == Phase::Showdown {
+                break;
+            }
+        }
+        self.resolution(false)?;
+
+        Ok(names
+            .iter()
+            .enumerate()
+            .map(|(seat, name)| {
+                let stack = self.names.iter().position(|n| n == name).map(|s| self.stacks[s]).unwrap_or(0);
+                stack - self.hand_start_stacks[seat]
+            })
+            .collect())
+    }
+
+    /// Rough strength of a player's hand for the native strategies: 2 strong, 1 medium, 0 weak.
+    /// Preflop, strong is a pair of tens or better or two aces-kings-queens, medium any other
+    /// pair or two cards from ten up. After the flop, strong is two pair or better, medium one pair.
+    fn hand_strength(&self, player: usize) -> PyResult<u8> {
+        if self.community_cards.is_empty() {
+            let mut values: Vec<usize> = self.player_cards[player]
+                .iter()
+                .filter_map(|card| card.chars().next().and_then(|rank| RANKS.find(rank)))
+                .collect();
+            values.sort_by_key(|&v| Reverse(v));
+            let paired = values.windows(2).any(|w| w[0] == w[1]);
+            let strength = if (paired && values[0] >= 8) || values[1] >= 10 {
+                2
+            } else if paired || values[1] >= 8 {
+                1
+            } else {
+                0
+            };
+            return Ok(strength);
+        }
+
+        let rank = self.rank_player(player, &self.community_cards)?;
+        let strength = if rank >= Rank::TwoPair(0) {
+            2
+        } else if rank >= Rank::OnePair(0) {
+            1
+        } else {
+            0
+        };
+        Ok(strength)
+    }
+
+    /// Add the hand just resolved to the statistics of the players who were dealt in
+    fn record_stats(&mut self) {
+        for i in 0..self.num_players {
+            let net = self.stacks[i] - self.hand_start_stacks[i];
+            let stats = self.stats.entry(self.names[i].clone()).or_default();
+            stats.hands += 1;
+            stats.vpip += self.voluntary[i] as u32;
+            stats.pfr += self.raised_preflop[i] as u32;
+            stats.won += (net > 0) as u32;
+            stats.net_chips += net as i64;
+        }
+    }
+
+    /// Discard the top card of the deck before a community deal, when cards are burned
+    fn burn(&mut self) -> PyResult<()> {
+        if self.burn_cards {
+            let card = self.deck.pop().ok_or_else(|| PyErr::new::<pyo3::exceptions::PyValueError, _>("Deck is empty"))?;
+            self.burned.push(card);
+        }
+        Ok(())
+    }
+
+    /// Log the event if verbose, and keep it in the history if it is recorded
+    fn emit(&mut self, event: Event, verbose: bool) -> PyResult<()> {
+        if verbose {
+            self.log(&event)?;
+        }
+        if self.record_history {
+            self.history.push((self.hand_number, event));
+        }
+        Ok(())
+    }
+
+    /// Give the event to `log_callback` with its level and its dict, or print it if there is none
+    fn log(&self, event: &Event) -> PyResult<()> {
+        match &self.log_callback {
+            Some(callback) => Python::with_gil(|py| {
+                callback.call1(py, (event.level(), event.to_dict(py)?))?;
+                Ok(())
+            }),
+            None => {
+                println!("{}", event);
+                Ok(())
+            }
+        }
+    }
+
+    /// Apply the action chosen by the current player. An action that is not among the legal
+    /// ones raises an error listing them, and nothing is played.
+    fn play_action(&mut self, action: Action, amount: i32, verbose: bool) -> PyResult<()> {
+        // Guard against betting rounds that never end
+        if self.actions_this_street >= self.max_actions_per_round {
+            let to_act: Vec<&String> = (0..self.num_players)
+                .filter(|&i| !self.folded[i] && !self.all_in[i])
+                .map(|i| &self.names[i])
+                .collect();
+            return Err(PyErr::new::<pyo3::exceptions::PyValueError, _>(format!(
+                "Error: more than {} actions on the {}, players still to act: {:?}",
+                self.max_actions_per_round,
+                self.current_phase.name(),
+                to_act
+            )));
+        }
+
+        // Amounts are totals for the street: a call matches the highest bet, as far as the stack goes,
+        // and a raise is a raise to the amount
+        let stack = self.stacks[self.current_player];
+        let amount = match action {
+            Action::AllIn => stack,
+            Action::Call => self.bets.iter().max().copied().unwrap_or(0).min(stack),
+            _ => amount,
+        };
+        if !self.is_legal(&action, amount) {
+            let offered = self.get_available_actions()?;
+            return Python::with_gil(|py| {
+                let legal: Vec<String> = offered.iter().map(|legal| legal.bind(py).to_string()).collect();
+                Err(PyErr::new::<pyo3::exceptions::PyValueError, _>(format!(
+                    "Error: {} can't {} {}, the legal actions are: {}",
+                    self.names[self.current_player],
+                    action.to_object(py),
+                    amount,
+                    legal.join(", "),
+                )))
+            });
+        }
+        self.actions_this_street += 1;
+        let player = self.names[self.current_player].clone();
+        self.emit(Event::PlayerAction { player, action: action.clone(), amount }, verbose)?;
+        self.hand_actions.push((self.current_player, action.name().to_string(), amount));
+
+        // An all in is a raise when it goes over the highest bet, a call otherwise
+        let action = match action {
+            Action::AllIn if amount > self.bets.iter().max().copied().unwrap_or(0) => Action::Raise,
+            Action::AllIn => Action::Call,
+            action => action,
+        };
+
+        if self.current_phase == Phase::Preflop {
+            match action {
+                Action::Call => self.voluntary[self.current_player] = true,
+                Action::Raise => {
+                    self.voluntary[self.current_player] = true;
+                    self.raised_preflop[self.current_player] = true;
+                }
+                _ => {}
+            }
+        }
+
+        match action {
+            Action::Fold => {
+                self.folded[self.current_player] = true;
+            }
+            Action::Check => {}
+            Action::Call | Action::AllIn => {
+                self.commit(self.current_player, amount - self.bets[self.current_player])?;
+            }
+            Action::Raise => {
+                let raise_amount = amount - self.bets.iter().max().copied().unwrap_or(0);
+                // A full raise reopens the betting and counts for the caps, a short all in doesn't
+                if raise_amount >= self.max_raise {
+                    self.may_raise.iter_mut().for_each(|may_raise| *may_raise = true);
+                    self.raises_this_street += 1;
+                    self.num_raises_this_hand += 1;
+                }
+                if raise_amount > self.max_raise {
+                    self.max_raise = raise_amount;
+                }
+                self.commit(self.current_player, amount - self.bets[self.current_player])?;
+                self.last_aggressor = Some(self.current_player);
+            }
+        }
+        // He may raise again only after a full raise. Forced bets are not actions.
+        self.may_raise[self.current_player] = false;
+        self.has_acted_this_round[self.current_player] = true;
+
+        // Nobody can have bet more than his stack
+        if let Some(i) = (0..self.num_players).find(|&i| self.bets[i] > self.stacks[i]) {
+            return Err(PyErr::new::<pyo3::exceptions::PyRuntimeError, _>(format!(
+                "Error: {} bets {} with a stack of {}",
+                self.names[i], self.bets[i], self.stacks[i]
+            )));
+        }
+        if self.debug_invariants {
+            self.check_invariants("an action")?;
+        }
+        Ok(())
+    }
+
+    /// Checks of `debug_invariants`, naming the step after which one fails: stacks and bets
+    /// within bounds, the pot made of the contributions, the chips of the hand all there and
+    /// the all in flags matching the chips behind
+    fn check_invariants(&self, after: &str) -> PyResult<()> {
+        let fail = |problem: String| {
+            Err(PyErr::new::<pyo3::exceptions::PyValueError, _>(format!(
+                "Error: after {} on the {}, {}",
+                after,
+                self.current_phase.name(),
+                problem
+            )))
+        };
+        for i in 0..self.num_players {
+            let name = &self.names[i];
+            if self.stacks[i] < 0 {
+                return fail(format!("{} has a stack of {}", name, self.stacks[i]));
+            }
+            if self.bets[i] < 0 || self.bets[i] > self.stacks[i] {
+                return fail(format!("{} bets {} with a stack of {}", name, self.bets[i], self.stacks[i]));
+            }
+            let behind = self.stacks[i] - self.bets[i];
+            if !self.folded[i] && self.all_in[i] != (behind == 0) {
+                return fail(format!("{} has {} behind but all in is {}", name, behind, self.all_in[i]));
+            }
+        }
+        let contributions = self.contributions.iter().sum::<i32>();
+        if self.pot != contributions {
+            return fail(format!("the pot is {} for {} contributed", self.pot, contributions));
+        }
+        let chips = self.stacks.iter().sum::<i32>() + self.pot;
+        let dealt = self.hand_start_stacks.iter().sum::<i32>();
+        if chips != dealt {
+            return fail(format!("{} chips are on the table for {} at the start of the hand", chips, dealt));
+        }
+        Ok(())
+    }
+
+    /// Deal the next streets until somebody has to act, or resolve the hand.
+    /// Return true if the hand is over.
+    fn finish_round(&mut self) -> PyResult<bool> {
+        loop {
+            if self.folded_out() {
+                self.resolution(false)?;
+                return Ok(true);
+            }
+
+            if self.betting_closed() {
+                self.run_out_board(false)?;
+            } else {
+                self.advance_phase(false)?;
+            }
+
+            if self.current_phase == Phase::Showdown {
+                self.resolution(false)?;
+                return Ok(true);
+            }
+
+            if self.seek_actor()? {
+                return Ok(false);
+            }
+        }
+    }
+
+    /// Score of each player still in the hand against a board, best first.
+    /// A lone player left takes the pot without his hand being ranked.
+    fn showdown_scores(&self, board: &[String]) -> PyResult<Vec<(String, u64)>> {
+        let contenders = self.folded.iter().filter(|&&b| !b).count();
+        let mut scores = Vec::new();
+        for i in 0..self.num_players {
+            if !self.folded[i] {
+                let rank = if contenders == 1 {
+                    0
+                } else {
+                    rank_score(&self.rank_player(i, board)?, self.short_deck)
+                };
+                scores.push((self.names[i].clone(), rank));
+            }
+        }
+        scores.sort_by_key(|x| Reverse(x.1));
+        Ok(scores)
+    }
+
+    /// Whether the betting is over for the hand: someone is all in and at most one player still
+    /// in the hand has chips behind
+    fn betting_closed(&self) -> bool {
+        let contenders = (0..self.num_players).filter(|&i| !self.folded[i]);
+        contenders.clone().count() >= 2
+            && contenders.clone().any(|i| self.all_in[i])
+            && contenders.filter(|&i| !self.all_in[i]).count() <= 1
+    }
+
+    /// Second board when the hand is run twice: the cards dealt before the betting closed,
+    /// completed from the rest of the deck
+    fn second_runout(&mut self, verbose: bool) -> PyResult<Option<Vec<String>>> {
+        let from = match self.runout_from {
+            Some(from) if self.run_it_twice && from < self.board_size() && !self.folded_out() => from,
+            _ => return Ok(None),
+        };
+
+        let mut board = self.community_cards[..from].to_vec();
+        while board.len() < self.board_size() {
+            let card = self.deck.pop().ok_or_else(|| PyErr::new::<pyo3::exceptions::PyValueError, _>("Deck is empty"))?;
+            board.push(card);
+        }
+        self.second_board = board.clone();
+        self.emit(Event::BoardDealt { cards: board[from..].to_vec() }, verbose)?;
+        Ok(Some(board))
+    }
+
+    /// Rank the best hand of a player: any five of his cards and the board in Hold'em, exactly
+    /// two of his cards and three of the board when he has more hole cards (Omaha)
+    fn rank_player(&self, player: usize, board: &[String]) -> PyResult<Rank> {
+        self.rank_hand(&self.player_cards[player], board)
+    }
+
+    /// Best hand of hole cards with the board, per the rules of the variant played
+    fn rank_hand(&self, hole: &[String], board: &[String]) -> PyResult<Rank> {
+        if self.hole_cards == 2 && !self.short_deck {
+            let cards = [hole, board].concat();
+            return Ok(parse_hand(&cards)?.rank());
+        }
+
+        // The hole cards and the board are split below, check no card appears in both first
+        parse_hand(&[hole, board].concat())?;
+        let hole: Vec<Card> = parse_hand(hole)?.iter().collect();
+        let board: Vec<Card> = parse_hand(board)?.iter().collect();
+        let hands = if self.hole_cards == 2 {
+            combinations(&[hole, board].concat(), 5)
+        } else {
+            let boards = combinations(&board, 3);
+            combinations(&hole, 2)
+                .into_iter()
+                .flat_map(|h| boards.iter().map(move |b| [h.clone(), b.clone()].concat()))
+                .collect()
+        };
+
+        hands
+            .iter()
+            .map(|five| rank_five(five, self.short_deck))
+            .max_by_key(|rank| rank_score(rank, self.short_deck))
+            .ok_or_else(|| PyErr::new::<pyo3::exceptions::PyValueError, _>("Error: not enough cards to make a hand"))
+    }
+
+    /// Stack a player starts a game with: the one given to `new` for him, else the initial stack
+    fn starting_stack(&self, name: &str) -> i32 {
+        self.initial_stacks.get(name).copied().unwrap_or(self.initial_stack)
+    }
+
+    /// Reward in the units of `reward_scale`
+    fn scale_reward(&self, chips: i32) -> f64 {
+        match self.reward_scale {
+            RewardScale::Chips => chips as f64,
+            RewardScale::BigBlinds => chips as f64 / self.big_blind as f64,
+            RewardScale::StartingStack => chips as f64 / self.initial_stack as f64,
+        }
+    }
+
+    /// Players who can still bet: neither folded nor all in, the current player always included
+    fn active_players(&self) -> usize {
+        (0..self.num_players)
+            .filter(|&i| !self.folded[i] && (!self.all_in[i] || i == self.current_player))
+            .count()
+    }
+
+    /// Whether everyone folded preflop to the big blind, nobody having put more than a blind in
+    fn is_walk(&self) -> bool {
+        self.current_phase == Phase::Preflop
+            && self.folded_out()
+            && !self.folded[self.bb_pos()]
+            && self.hand_actions.iter().all(|(_, action, _)| !matches!(action.as_str(), "call" | "raise" | "allin"))
+    }
+
+    /// Whether every player but one has folded
+    fn folded_out(&self) -> bool {
+        self.folded.iter().filter(|&&b| b).count() == self.num_players - 1
+    }
+
+    /// Whether the hand has been played to its end
+    fn hand_over(&self) -> bool {
+        self.current_phase == Phase::Showdown || self.folded_out()
+    }
+}
+
+/// Card with the symbol of its suit, e.g. "A♠" for "As"
+fn pretty_card(card: &str) -> String {
+    let mut chars = card.chars();
+    let rank = chars.next().unwrap_or('?');
+    let suit = match chars.next() {
+        Some('s') => '♠',
+        Some('h') => '♥',
+        Some('d') => '♦',
+        Some('c') => '♣',
+        Some(other) => other,
+        None => '?',
+    };
+    format!("{}{}", rank, suit)
+}
+
+/// Name of a player when none is given: player_A to player_Z, then player_26, player_27...
+fn default_name(seat: usize) -> String {
+    if seat < 26 {
+        format!("player_{}", (b'A' + seat as u8) as char)
+    } else {
+        format!("player_{}", seat)
+    }
+}
+
+/// Read an action tuple returned by an agent: its type and amount (0 for fold and check)
+fn extract_action(action: &Bound<'_, PyAny>) -> PyResult<(Action, i32)> {
+    let action_type = action.get_item(0)?.extract::<String>()?;
+
+    match action_type.as_str() {
+        "fold" => Ok((Action::Fold, 0)),
+        "check" => Ok((Action::Check, 0)),
+        "call" => Ok((Action::Call, action.get_item(1)?.extract::<i32>()?)),
+        "raise" => Ok((Action::Raise, action.get_item(1)?.extract::<i32>()?)),
+        // The amount is the whole stack of the player, whatever the agent gives
+        "allin" => Ok((Action::AllIn, 0)),
+        _ => Err(PyErr::new::<pyo3::exceptions::PyValueError, _>(
+            "Error: not valid action",
+        )),
+    }
+}
+
+/// Observations, rewards and ends of hand of a batch of tables
+type BatchStep = (Vec<Py<PyDict>>, Vec<f64>, Vec<bool>);
+
+/// Independent tables stepped together, for batched rollouts.
+/// The tables are copied in at construction, the `PokerEnv` objects given to `new` are left
+/// untouched by `reset_all` and `step_all`, the played tables are read back through `envs`.
+#[pyclass]
+pub struct VecPokerEnv {
+    envs: Vec<PokerEnv>,
+}
+
+#[pymethods]
+impl VecPokerEnv {
+    #[new]
+    pub fn new(envs: Vec<PokerEnv>) -> PyResult<Self> {
+        if envs.is_empty() {
+            return Err(PyErr::new::<pyo3::exceptions::PyValueError, _>(
+                "Error: at least one table is needed",
+            ));
+        }
+        Ok(VecPokerEnv { envs })
+    }
+
+    #[getter]
+    pub fn num_envs(&self) -> usize {
+        self.envs.len()
+    }
+
+    /// Copies of the tables as they are now
+    #[getter]
+    pub fn envs(&self) -> Vec<PokerEnv> {
+        self.envs.clone()
+    }
+
+    pub fn __len__(&self) -> usize {
+        self.envs.len()
+    }
+
+    /// Deal a new hand on every table, from the starting stacks with `reset_stacks`
+    #[pyo3(signature = (reset_stacks=false))]
+    pub fn reset_all(&mut self, reset_stacks: bool) -> PyResult<()> {
+        for env in self.envs.iter_mut() {
+            env.reset(reset_stacks)?;
+        }
+        Ok(())
+    }
+
+    /// Observation of the current player of each table
+    pub fn get_states(&mut self) -> PyResult<Vec<Py<PyDict>>> {
+        self.envs.iter_mut().map(|env| env.get_state()).collect()
+    }
+
+    /// Apply one action per table, as `PokerEnv.step` does, the tables being played in parallel.
+    /// Return the observations, the rewards and whether each hand is over. Tables whose hand is
+    /// over are not dealt again, `reset_all` must be called.
+    pub fn step_all(&mut self, py: Python, actions: Vec<Py<PyTuple>>) -> PyResult<BatchStep> {
+        if actions.len() != self.envs.len() {
+            return Err(PyErr::new::<pyo3::exceptions::PyValueError, _>(format!(
+                "Error: {} actions given for {} tables",
+                actions.len(),
+                self.envs.len()
+            )));
+        }
+        let actions = actions
+            .iter()
+            .map(|action| extract_action(action.bind(py).as_any()))
+            .collect::<PyResult<Vec<_>>>()?;
+
+        let envs = &mut self.envs;
+        let results = py.allow_threads(|| {
+            envs.par_iter_mut()
+                .zip(actions)
+                .map(|(env, (action_type, amount))| env.advance(action_type, amount))
+                .collect::<PyResult<Vec<_>>>()
+        })?;
+
+        let obs = self.get_states()?;
+        let rewards = results.iter().zip(&self.envs).map(|(&(_, reward, _), env)| env.scale_reward(reward)).collect();
+        let dones = results.iter().map(|&(_, _, done)| done).collect();
+        Ok((obs, rewards, dones))
+    }
+}
+
+/// Cards of `cards` that are not in `removed`
+fn without(cards: &[String], removed: &[String]) -> Vec<String> {
+    cards.iter().filter(|card| !removed.contains(card)).cloned().collect()
+}
+
+/// Parse card strings ("Ah", "Td", ...) into a hand, rejecting malformed and duplicate cards
+fn parse_hand(cards: &[String]) -> PyResult<Hand> {
+    let mut hand = Hand::new();
+    for card in cards {
+        let parsed = match card.len() {
+            2 => Card::try_from(card.as_str()).ok(),
+            _ => None,
+        };
+        let parsed = parsed.ok_or_else(|| {
+            PyErr::new::<pyo3::exceptions::PyValueError, _>(format!("Error: malformed card {:?}", card))
+        })?;
+        if !hand.insert(parsed) {
+            return Err(PyErr::new::<pyo3::exceptions::PyValueError, _>(
+                format!("Error: duplicate card {:?}", card),
+            ));
+        }
+    }
+    Ok(hand)
+}
+
+/// Name of the category of a hand rank
+fn rank_category(rank: &Rank) -> &'static str {
+    match rank {
+        Rank::HighCard(_) => "HighCard",
+        Rank::OnePair(_) => "OnePair",
+        Rank::TwoPair(_) => "TwoPair",
+        Rank::ThreeOfAKind(_) => "ThreeOfAKind",
+        Rank::Straight(_) => "Straight",
+        Rank::Flush(_) => "Flush",
+        Rank::FullHouse(_) => "FullHouse",
+        Rank::FourOfAKind(_) => "FourOfAKind",
+        Rank::StraightFlush(_) => "StraightFlush",
+    }
+}
+
+/// Human-readable rank: its category followed by the values that decide it, e.g. "TwoPair (K, 9, 4)"
+fn describe_rank(rank: &Rank) -> String {
+    // Values of a 13 bits set, highest first
+    let values = |set: u32| -> Vec<String> {
+        (0..13u8)
+            .rev()
+            .filter(|v| set & (1 << v) != 0)
+            .map(|v| char::from(Value::from(v)).to_string())
+            .collect()
+    };
+
+    let kickers = match *rank {
+        // Straights are ranked by their high card, the wheel being 0 (five high)
+        Rank::Straight(r) | Rank::StraightFlush(r) => values(1 << (r + 3)),
+        Rank::HighCard(r) | Rank::Flush(r) => values(r),
+        Rank::OnePair(r)
+        | Rank::TwoPair(r)
+        | Rank::ThreeOfAKind(r)
+        | Rank::FullHouse(r)
+        | Rank::FourOfAKind(r) => {
+            let mut v = values(r >> 13);
+            v.extend(values(r & 0x1FFF));
+            v
+        }
+    };
+
+    format!("{} ({})", rank_category(rank), kickers.join(", "))
+}
+
+/// Every way to pick `k` cards among `cards`
+fn combinations<T: Clone>(cards: &[T], k: usize) -> Vec<Vec<T>> {
+    if k == 0 {
+        return vec![Vec::new()];
+    }
+    let mut result = Vec::new();
+    for (i, card) in cards.iter().enumerate() {
+        for mut rest in combinations(&cards[i + 1..], k - 1) {
+            rest.insert(0, card.clone());
+            result.push(rest);
+        }
+    }
+    result
+}
+
+/// Runouts sampled for the all in equities of `allin_ev_rewards`, all of them when there are fewer
+const ALLIN_EV_SAMPLES: usize = 10000;
+
+/// Values of A-6-7-8-9, the lowest straight in short deck
+const SHORT_DECK_WHEEL: u32 = 0b1_0000_1111_0000;
+
+/// Rank exactly five cards. In short deck A-6-7-8-9 is a straight, just below 6-7-8-9-T.
+fn rank_five(cards: &[Card], short_deck: bool) -> Rank {
+    let rank = Hand::new_with_cards(cards.to_vec()).rank_five();
+    if short_deck {
+        let values = cards.iter().fold(0u32, |set, c| set | (1 << c.value as u32));
+        if values == SHORT_DECK_WHEEL {
+            // Ranked as a nine high straight, which can't otherwise exist without the 5
+            return match rank {
+                Rank::Flush(_) => Rank::StraightFlush(4),
+                _ => Rank::Straight(4),
+            };
+        }
+    }
+    rank
+}
+
+/// Comparable strength of a rank, the higher the better. In short deck a flush beats a full house.
+fn rank_score(rank: &Rank, short_deck: bool) -> u64 {
+    let (category, value) = match *rank {
+        Rank::HighCard(v) => (0, v),
+        Rank::OnePair(v) => (1, v),
+        Rank::TwoPair(v) => (2, v),
+        Rank::ThreeOfAKind(v) => (3, v),
+        Rank::Straight(v) => (4, v),
+        Rank::Flush(v) => (if short_deck { 6 } else { 5 }, v),
+        Rank::FullHouse(v) => (if short_deck { 5 } else { 6 }, v),
+        Rank::FourOfAKind(v) => (7, v),
+        Rank::StraightFlush(v) => (8, v),
+    };
+    (category << 32) | value as u64
+}
+
+/// Ranks and suits of the cards, in the order of a new deck
+const RANKS: &str = "23456789TJQKA";
+const SUITS: &str = "hdcs";
+
+/// Cards of a new deck, from 6 to A only in short deck
+fn new_deck(short_deck: bool) -> Vec<String> {
+    let lowest = if short_deck { 4 * SUITS.len() } else { 0 };
+    (lowest..52).map(index_to_card_string).collect()
+}
+
+/// Cards in sorted order, to compare decks whatever their order
+fn sorted_cards(cards: &[String]) -> Vec<String> {
+    let mut sorted = cards.to_vec();
+    sorted.sort();
+    sorted
+}
+
+fn index_to_card_string(index: usize) -> String {
+    let rank = RANKS.as_bytes()[index / SUITS.len()] as char;
+    let suit = SUITS.as_bytes()[index % SUITS.len()] as char;
+    format!("{}{}", rank, suit)
+}
+
+/// Index of a card from 0 to 51, rank-major in the order of a new deck: 2h=0, 2d=1, 2c=2,
+/// 2s=3, 3h=4, ..., As=51
+#[pyfunction]
+pub fn card_to_index(card: String) -> PyResult<usize> {
+    let mut chars = card.chars();
+    let (rank, suit) = match (chars.next(), chars.next(), chars.next()) {
+        (Some(rank), Some(suit), None) => (RANKS.find(rank), SUITS.find(suit)),
+        _ => (None, None),
+    };
+    match (rank, suit) {
+        (Some(rank), Some(suit)) => Ok(rank * SUITS.len() + suit),
+        _ => Err(PyErr::new::<pyo3::exceptions::PyValueError, _>(
+            format!("Error: malformed card {:?}", card),
+        )),
+    }
+}
+
+/// Multi-hot vector of 52 floats with a 1.0 at the index of each card
+fn one_hot(cards: &[String]) -> PyResult<Vec<f32>> {
+    let mut encoded = vec![0.0; 52];
+    for card in cards {
+        encoded[card_to_index(card.clone())?] = 1.0;
+    }
+    Ok(encoded)
+}
+
+/// Card of an index given by `card_to_index`
+#[pyfunction]
+pub fn index_to_card(i: usize) -> PyResult<String> {
+    if i >= 52 {
+        return Err(PyErr::new::<pyo3::exceptions::PyValueError, _>(
+            format!("Error: card index must be below 52, got {}", i),
+        ));
+    }
+    Ok(index_to_card_string(i))
+}
+
+/// Heads-up equity of each starting hand against a random hand, rows and columns from A down
+/// to 2: pairs on the diagonal, suited hands above it (row of the high card) and offsuit hands
+/// below it (column of the high card). Precomputed by simulation, 400k runouts per hand.
+const PREFLOP_EQUITY: [[f64; 13]; 13] = [
+    [0.852, 0.670, 0.662, 0.652, 0.645, 0.627, 0.619, 0.610, 0.599, 0.599, 0.590, 0.582, 0.574],
+    [0.653, 0.824, 0.633, 0.625, 0.618, 0.599, 0.583, 0.574, 0.568, 0.558, 0.547, 0.540, 0.532],
+    [0.645, 0.614, 0.800, 0.602, 0.594, 0.576, 0.560, 0.543, 0.537, 0.528, 0.519, 0.508, 0.502],
+    [0.636, 0.607, 0.581, 0.775, 0.575, 0.556, 0.540, 0.524, 0.507, 0.499, 0.490, 0.482, 0.474],
+    [0.627, 0.598, 0.573, 0.552, 0.751, 0.540, 0.524, 0.507, 0.490, 0.472, 0.466, 0.456, 0.448],
+    [0.608, 0.578, 0.553, 0.531, 0.516, 0.720, 0.509, 0.490, 0.473, 0.457, 0.439, 0.434, 0.425],
+    [0.599, 0.560, 0.536, 0.516, 0.497, 0.481, 0.691, 0.478, 0.463, 0.444, 0.427, 0.410, 0.403],
+    [0.589, 0.553, 0.518, 0.496, 0.480, 0.463, 0.449, 0.663, 0.453, 0.437, 0.418, 0.400, 0.382],
+    [0.578, 0.541, 0.510, 0.479, 0.462, 0.446, 0.434, 0.423, 0.633, 0.430, 0.413, 0.396, 0.377],
+    [0.576, 0.535, 0.501, 0.471, 0.442, 0.427, 0.413, 0.405, 0.399, 0.603, 0.413, 0.398, 0.378],
+    [0.568, 0.523, 0.490, 0.462, 0.435, 0.406, 0.394, 0.387, 0.379, 0.383, 0.571, 0.387, 0.368],
+    [0.557, 0.515, 0.482, 0.453, 0.426, 0.401, 0.375, 0.366, 0.361, 0.364, 0.351, 0.538, 0.359],
+    [0.549, 0.505, 0.473, 0.443, 0.416, 0.391, 0.368, 0.347, 0.341, 0.344, 0.331, 0.323, 0.504],
+];
+
+/// Equity of a two-card starting hand against a random hand, from 0.32 (32o) to 0.85 (AA).
+/// The cards are normalized to their class among the 169 (pair, suited or offsuit), whose value
+/// is read from a table precomputed by simulation.
+#[pyfunction]
+pub fn preflop_strength(hole: Vec<String>) -> PyResult<f64> {
+    if hole.len() != 2 {
+        return Err(PyErr::new::<pyo3::exceptions::PyValueError, _>(
+            format!("Error: expected 2 hole cards, got {}", hole.len()),
+        ));
+    }
+    parse_hand(&hole)?;
+
+    let first = card_to_index(hole[0].clone())?;
+    let second = card_to_index(hole[1].clone())?;
+    let (high, low) = (first.max(second) / SUITS.len(), first.min(second) / SUITS.len());
+    let suited = first % SUITS.len() == second % SUITS.len();
+
+    let (row, column) = if suited { (12 - high, 12 - low) } else { (12 - low, 12 - high) };
+    Ok(PREFLOP_EQUITY[row][column])
+}
+
+/// Rank the best five cards among 5 to 7 cards, e.g. evaluate_hand(["Ah", "Ad", "Kc", "Ks", "2d"])
+/// returns "TwoPair (A, K, 2)"
+#[pyfunction]
+pub fn evaluate_hand(cards: Vec<String>) -> PyResult<String> {
+    if !(5..=7).contains(&cards.len()) {
+        return Err(PyErr::new::<pyo3::exceptions::PyValueError, _>(
+            format!("Error: expected 5 to 7 cards, got {}", cards.len()),
+        ));
+    }
+    let hand = parse_hand(&cards)?;
+    Ok(describe_rank(&hand.rank()))
+}
+
+/// Strength of the best five cards among 5 to 7 cards as a single integer, the one resolution
+/// compares: a stronger hand always scores higher and equal hands (a split pot) score the same.
+/// In short deck, where a flush beats a full house and A-6-7-8-9 is a straight, pass `short_deck`.
+#[pyfunction]
+#[pyo3(signature = (cards, short_deck=false))]
+pub fn hand_score(cards: Vec<String>, short_deck: bool) -> PyResult<u64> {
+    if !(5..=7).contains(&cards.len()) {
+        return Err(PyErr::new::<pyo3::exceptions::PyValueError, _>(
+            format!("Error: expected 5 to 7 cards, got {}", cards.len()),
+        ));
+    }
+    let hand = parse_hand(&cards)?;
+    if !short_deck {
+        return Ok(rank_score(&hand.rank(), false));
+    }
+    let cards: Vec<Card> = hand.iter().collect();
+    Ok(combinations(&cards, 5)
+        .iter()
+        .map(|five| rank_score(&rank_five(five, true), true))
+        .max()
+        .unwrap_or(0))
+}
+
+/// Monte Carlo estimate of the probability for `hole` to win against `num_opponents` random hands,
+/// the board being completed from the unused cards. Split pots count as a fraction of a win.
+#[pyfunction]
+#[pyo3(signature = (hole, board, num_opponents, iterations, seed=None))]
+pub fn equity(
+    hole: Vec<String>,
+    board: Vec<String>,
+    num_opponents: usize,
+    iterations: usize,
+    seed: Option<u64>,
+) -> PyResult<f64> {
+    if hole.len() != 2 || board.len() > 5 {
+        return Err(PyErr::new::<pyo3::exceptions::PyValueError, _>(
+            "Error: expected 2 hole cards and at most 5 board cards",
+        ));
+    }
+    if num_opponents == 0 || iterations == 0 {
+        return Err(PyErr::new::<pyo3::exceptions::PyValueError, _>(
+            "Error: num_opponents and iterations must be positive",
+        ));
+    }
+
+    let known = parse_hand(&[hole.clone(), board.clone()].concat())?;
+    let hero = parse_hand(&hole)?;
+    let table = parse_hand(&board)?;
+
+    let mut deck: Vec<Card> = (0..52u8).map(Card::from).filter(|c| !known.contains(c)).collect();
+    let missing = 5 - board.len();
+    let needed = missing + 2 * num_opponents;
+    if needed > deck.len() {
+        return Err(PyErr::new::<pyo3::exceptions::PyValueError, _>(
+            "Error: not enough cards left for this many opponents",
+        ));
+    }
+
+    let mut rng = match seed {
+        Some(seed) => ChaCha12Rng::seed_from_u64(seed),
+        None => ChaCha12Rng::from_entropy(),
+    };
+
+    let mut wins = 0.0;
+    for _ in 0..iterations {
+        let (drawn, _) = deck.partial_shuffle(&mut rng, needed);
+
+        let mut full_board = table;
+        full_board.extend(drawn[..missing].iter().copied());
+
+        let mut hero_hand = full_board;
+        hero_hand.extend(hero.iter());
+        let hero_rank = hero_hand.rank();
+
+        let mut tied = 1;
+        let mut lost = false;
+        for opponent in drawn[missing..].chunks(2) {
+            let mut opponent_hand = full_board;
+            opponent_hand.extend(opponent.iter().copied());
+            let rank = opponent_hand.rank();
+            if rank > hero_rank {
+                lost = true;
+                break;
+            }
+            if rank == hero_rank {
+                tied += 1;
+            }
+        }
+
+        if !lost {
+            wins += 1.0 / tied as f64;
+        }
+    }
+
+    Ok(wins / iterations as f64)
+}
+
+#[pymodule]
+fn rust_poker_env(_py: Python, m: &Bound<'_, PyModule>) -> PyResult<()> {
+    m.add_class::<Action>()?;
+    m.add_class::<Phase>()?;
+    m.add_class::<BettingMode>()?;
+    m.add_class::<Encoding>()?;
+    m.add_class::<RewardMode>()?;
+    m.add_class::<RewardScale>()?;
+    m.add_class::<AnteMode>()?;
+    m.add_class::<ObservationLevel>()?;
+    m.add_class::<NativeStrategy>()?;
+    m.add_class::<NativeAgent>()?;
+    m.add_class::<AbstractAction>()?;
+    m.add_class::<PokerConfig>()?;
+    m.add_class::<PokerEnv>()?;
+    m.add_class::<VecPokerEnv>()?;
+    m.add_function(wrap_pyfunction!(evaluate_hand, m)?)?;
+    m.add_function(wrap_pyfunction!(hand_score, m)?)?;
+    m.add_function(wrap_pyfunction!(equity, m)?)?;
+    m.add_function(wrap_pyfunction!(card_to_index, m)?)?;
+    m.add_function(wrap_pyfunction!(index_to_card, m)?)?;
+    m.add_function(wrap_pyfunction!(preflop_strength, m)?)?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Run a test with the GIL, the interpreter started if needed
+    fn with_py<R>(f: impl FnOnce(Python) -> R) -> R {
+        pyo3::prepare_freethreaded_python();
+        Python::with_gil(f)
+    }
+
+    /// Table of `players` seats without agents, its config built through the Python constructor
+    /// so that every option not given in `options` keeps its default
+    fn table(py: Python, players: usize, blinds: (i32, i32), stack: i32, options: &[(&str, PyObject)]) -> PokerEnv {
+        seated(py, vec![py.None(); players], blinds, stack, options)
+    }
+
+    /// Table of the agents given
+    fn seated(py: Python, agents: Vec<PyObject>, blinds: (i32, i32), stack: i32, options: &[(&str, PyObject)]) -> PokerEnv {
+        let kwargs = PyDict::new_bound(py);
+        for (key, value) in options {
+            kwargs.set_item(key, value).unwrap();
+        }
+        let config: PokerConfig = py.get_type_bound::<PokerConfig>().call((), Some(&kwargs)).unwrap().extract().unwrap();
+        PokerEnv::new(py, agents, blinds.0, blinds.1, stack, Some(config)).unwrap()
+    }
+
+    /// Bounds of the raise offered to the current player, if any
+    fn raise_range(env: &mut PokerEnv) -> Option<(i32, i32)> {
+        Python::with_gil(|py| {
+            env.get_available_actions().unwrap().into_iter().find_map(|action| {
+                let action = action.bind(py);
+                match action.get_item(0).unwrap().extract::<String>().unwrap().as_str() {
+                    "raise" => Some(action.get_item(1).unwrap().extract().unwrap()),
+                    _ => None,
+                }
+            })
+        })
+    }
+
+    /// Play an action for the current player with `step`, return whether the hand is over
+    fn play(env: &mut PokerEnv, action: Action, amount: i32) -> PyResult<bool> {
+        Python::with_gil(|py| {
+            let action = PyTuple::new_bound(py, [action.to_object(py), amount.to_object(py)]);
+            Ok(env.step(action.into())?.2)
+        })
+    }
+
+    fn act(env: &mut PokerEnv, action: Action, amount: i32) -> bool {
+        play(env, action, amount).unwrap()
+    }
+
+    /// The check or the call offered to the current player, a check if nothing is
+    fn passive(env: &mut PokerEnv) -> Py<PyTuple> {
+        Python::with_gil(|py| {
+            let offered = env.get_available_actions().unwrap().into_iter().find(|action| {
+                matches!(action.bind(py).get_item(0).unwrap().extract::<String>().unwrap().as_str(), "check" | "call")
+            });
+            offered.unwrap_or_else(|| PyTuple::new_bound(py, [Action::Check.to_object(py)]).into())
+        })
+    }
+
+    /// Check or call until the hand is over
+    fn check_down(env: &mut PokerEnv) {
+        loop {
+            let action = passive(env);
+            if env.step(action).unwrap().2 {
+                break;
+            }
+        }
+    }
+
+    /// Deck of the game of `env` dealing `hands` from seat 0 on, then `board`
+    fn rigged(env: &PokerEnv, hands: &[&[&str]], board: &[&str]) -> Vec<String> {
+        let dealt: Vec<String> = hands.concat().into_iter().chain(board.iter().copied()).map(String::from).collect();
+        let mut deck: Vec<String> = env.base_deck.iter().filter(|card| !dealt.contains(card)).cloned().collect();
+        deck.extend(dealt.into_iter().rev());
+        deck
+    }
+
+    /// Raise the bet of the current player to `to` and pass the turn on
+    fn raise_to(env: &mut PokerEnv, to: i32) {
+        let raiser = env.current_player;
+        env.apply_bet(raiser, to).unwrap();
+        env.raises_this_street += 1;
+        env.current_player = (raiser + 1) % env.num_players;
+    }
+
+    #[test]
+    fn pot_limit_caps_the_raise_at_the_pot_after_the_call() {
+        with_py(|py| {
+            let mut env = table(py, 3, (1, 2), 500, &[("betting_mode", BettingMode::PotLimit.into_py(py))]);
+            // Blinds of 1 and 2: calling 2 makes a pot of 5, a raise to 2 + 5
+            assert_eq!(env.pot_size(), 3);
+            assert_eq!(raise_range(&mut env), Some((4, 7)));
+
+            // To 7: the small blind calls 6 for a pot of 16, a raise to 7 + 16
+            let raiser = env.current_player;
+            env.apply_bet(raiser, 7).unwrap();
+            env.current_player = (env.dealer_pos + 1) % env.num_players;
+            assert_eq!(env.bets[env.current_player], 1);
+            assert_eq!(raise_range(&mut env).unwrap().1, 23);
+        });
+    }
+
+    #[test]
+    fn pot_limit_raise_is_bounded_by_the_stack() {
+        with_py(|py| {
+            let mut env = table(py, 2, (10, 20), 50, &[("betting_mode", BettingMode::PotLimit.into_py(py))]);
+            // The small blind has 10 in, the pot limit of 20 + 30 + 10 is over his stack
+            assert_eq!(raise_range(&mut env), Some((40, 50)));
+            let no_limit = raise_range(&mut table(py, 2, (10, 20), 500, &[])).unwrap();
+            assert_eq!(no_limit, (40, 500));
+        });
+    }
+
+    #[test]
+    fn fixed_limit_raises_by_the_small_then_the_big_bet_up_to_the_cap() {
+        with_py(|py| {
+            let mut env = table(py, 2, (1, 2), 100, &[("betting_mode", BettingMode::FixedLimit.into_py(py))]);
+            // Preflop a single raise size of one small bet, four raises at most
+            for to in [4, 6, 8, 10] {
+                assert_eq!(raise_range(&mut env), Some((to, to)));
+                raise_to(&mut env, to);
+            }
+            assert_eq!(raise_range(&mut env), None);
+            env.bets = vec![10, 10];
+
+            // A small bet on the flop, a big bet on the turn
+            env.advance_phase(false).unwrap();
+            let bet = *env.bets.iter().max().unwrap();
+            assert_eq!(raise_range(&mut env), Some((bet + 2, bet + 2)));
+            env.advance_phase(false).unwrap();
+            assert_eq!(raise_range(&mut env), Some((bet + 4, bet + 4)));
+            raise_to(&mut env, bet + 4);
+            assert_eq!(raise_range(&mut env), Some((bet + 8, bet + 8)));
+        });
+    }
+
+    #[test]
+    fn uncalled_raise_goes_back_to_the_raiser() {
+        with_py(|py| {
+            let mut env = table(py, 3, (1, 2), 200, &[]);
+            let raiser = env.current_player;
+            raise_to(&mut env, 100);
+            for seat in (0..3).filter(|&seat| seat != raiser) {
+                env.folded[seat] = true;
+            }
+            env.resolution(false).unwrap();
+            // Only the blinds were contested
+            assert_eq!(env.stacks[raiser], 203);
+            assert_eq!(env.stacks.iter().sum::<i32>(), 600);
+        });
+    }
+
+    #[test]
+    fn step_refuses_illegal_actions() {
+        with_py(|py| {
+            let mut env = table(py, 3, (1, 2), 100, &[]);
+            let seat = env.current_player;
+            let before = (env.bets.clone(), env.folded.clone());
+
+            // Facing the big blind, a check, a raise below the minimum or beyond the stack
+            let check = play(&mut env, Action::Check, 0).unwrap_err();
+            assert!(check.to_string().contains("can't check"));
+            assert!(check.to_string().contains("('call', 2), ('raise', (4, 100))"));
+            assert!(play(&mut env, Action::Raise, 3).is_err());
+            assert!(play(&mut env, Action::Raise, 101).is_err());
+            assert_eq!((env.bets.clone(), env.folded.clone()), before);
+            assert_eq!(env.current_player, seat);
+
+            // The same player then plays a legal raise
+            assert!(!act(&mut env, Action::Raise, 4));
+            assert_eq!(env.bets[seat], 4);
+            assert_ne!(env.current_player, seat);
+        });
+    }
+
+    #[test]
+    fn evaluate_hand_ranks_the_best_five_of_five_to_seven_cards() {
+        let cards = |list: &[&str]| list.iter().map(|card| card.to_string()).collect::<Vec<_>>();
+        assert_eq!(evaluate_hand(cards(&["Ah", "Ad", "Kc", "Ks", "2d"])).unwrap(), "TwoPair (A, K, 2)");
+        assert!(evaluate_hand(cards(&["Ah", "Kh", "9h", "4h", "2c", "3h"])).unwrap().starts_with("Flush"));
+        assert!(evaluate_hand(cards(&["Ah", "Ad", "Ac", "Ks", "Kd", "2h", "3h"])).unwrap().starts_with("FullHouse"));
+
+        assert!(evaluate_hand(cards(&["Ah", "Ad", "Kc", "Ks"])).is_err());
+        assert!(evaluate_hand(cards(&["Ah", "Ah", "Kc", "Ks", "2d"])).is_err());
+        assert!(evaluate_hand(cards(&["Ah", "Ad", "Kc", "Ks", "1x"])).is_err());
+    }
+
+    #[test]
+    fn rigged_deal_gives_the_showdown_to_the_full_house() {
+        with_py(|py| {
+            let mut env = table(py, 2, (1, 2), 100, &[]);
+            let board = ["Kh", "9h", "4h", "Ks", "2c"];
+            let deck = rigged(&env, &[&["Ah", "3h"], &["Kd", "9c"]], &board);
+            env.rig_next_deal(deck.clone()).unwrap();
+            env.reset(false).unwrap();
+            assert_eq!(env.player_cards, vec![vec!["Ah", "3h"], vec!["Kd", "9c"]]);
+
+            check_down(&mut env);
+            assert_eq!(env.community_cards, board);
+            // The flush of seat 0 loses to the full house of seat 1
+            assert_eq!(env.stacks, vec![98, 102]);
+
+            let mut short = deck;
+            short.pop();
+            assert!(env.rig_next_deal(short).is_err());
+        });
+    }
+
+    #[test]
+    fn heads_up_dealer_posts_the_small_blind_and_acts_first_preflop() {
+        with_py(|py| {
+            let mut env = table(py, 2, (1, 2), 100, &[]);
+            let dealer = env.dealer_pos;
+            let other = 1 - dealer;
+            assert_eq!((env.bets[dealer], env.bets[other]), (1, 2));
+
+            let mut actors = Vec::new();
+            loop {
+                actors.push((env.current_phase.clone(), env.current_player));
+                let action = passive(&mut env);
+                if env.step(action).unwrap().2 {
+                    break;
+                }
+            }
+            let expected = vec![
+                (Phase::Preflop, dealer),
+                (Phase::Preflop, other),
+                (Phase::Flop, other),
+                (Phase::Flop, dealer),
+                (Phase::Turn, other),
+                (Phase::Turn, dealer),
+                (Phase::River, other),
+                (Phase::River, dealer),
+            ];
+            assert_eq!(actors, expected);
+        });
+    }
+
+    #[test]
+    fn big_blind_keeps_his_option_in_a_limped_pot() {
+        with_py(|py| {
+            let mut env = table(py, 3, (1, 2), 100, &[]);
+            let big_blind = env.bb_pos();
+            for _ in 0..2 {
+                let call = passive(&mut env);
+                env.step(call).unwrap();
+            }
+            // Everyone has matched his blind, he is asked all the same
+            assert_eq!(env.current_phase, Phase::Preflop);
+            assert_eq!(env.current_player, big_blind);
+            assert!(raise_range(&mut env).is_some());
+            act(&mut env, Action::Check, 0);
+            assert_eq!(env.current_phase, Phase::Flop);
+        });
+    }
+
+    #[test]
+    fn big_blind_has_to_act_again_after_a_raise() {
+        with_py(|py| {
+            let mut env = table(py, 3, (1, 2), 100, &[]);
+            let big_blind = env.bb_pos();
+            act(&mut env, Action::Raise, 6);
+            act(&mut env, Action::Fold, 0);
+            assert_eq!(env.current_player, big_blind);
+            assert!(play(&mut env, Action::Check, 0).is_err());
+            act(&mut env, Action::Call, 6);
+            assert_eq!(env.current_phase, Phase::Flop);
+        });
+    }
+
+    #[test]
+    fn multiway_all_in_runs_the_board_out_and_ends() {
+        with_py(|py| {
+            let mut env = table(py, 4, (1, 2), 100, &[]);
+            env.stacks = vec![50, 80, 120, 200];
+            env.reset(false).unwrap();
+            let first = env.current_player;
+            let mut done = false;
+            for k in 0..4 {
+                done = match (first + k) % 4 {
+                    3 => act(&mut env, Action::Fold, 0),
+                    seat => {
+                        let stack = env.stacks[seat];
+                        match raise_range(&mut env) {
+                            Some(_) => act(&mut env, Action::Raise, stack),
+                            None => act(&mut env, Action::Call, stack),
+                        }
+                    }
+                };
+                if done {
+                    break;
+                }
+            }
+            assert!(done);
+            assert_eq!(env.community_cards.len(), 5);
+            assert_eq!(env.current_phase, Phase::Showdown);
+            assert_eq!(env.stacks.iter().sum::<i32>(), 450);
+            assert!(play(&mut env, Action::Check, 0).is_err());
+        });
+    }
+
+    /// Fold every player but the last one, ending the hand
+    fn fold_out(env: &mut PokerEnv) {
+        while !act(env, Action::Fold, 0) {}
+    }
+
+    #[test]
+    fn button_moves_on_over_the_players_left_after_kill() {
+        with_py(|py| {
+            let mut env = table(py, 5, (1, 2), 100, &[]);
+            let order = env.names.clone();
+            let after = |name: &String, k: usize| order[(order.iter().position(|n| n == name).unwrap() + k) % 5].clone();
+
+            // A player after the button leaves: the button goes to the next one still there
+            let dealer = env.names[env.dealer_pos].clone();
+            fold_out(&mut env);
+            let next = after(&dealer, 1);
+            let seat = env.names.iter().position(|n| *n == next).unwrap();
+            env.kill(seat).unwrap();
+            env.reset(false).unwrap();
+            assert_eq!(env.names[env.dealer_pos], after(&dealer, 2));
+
+            // The dealer himself leaves: the button goes to the player after him
+            let dealer = env.names[env.dealer_pos].clone();
+            fold_out(&mut env);
+            let seat = env.dealer_pos;
+            env.kill(seat).unwrap();
+            env.reset(false).unwrap();
+            assert_eq!(env.names[env.dealer_pos], after(&dealer, 1));
+            assert_eq!(env.num_players, 3);
+            assert!(env.dealer_pos < 3);
+        });
+    }
+
+    #[test]
+    fn odd_chip_of_a_three_way_split_goes_first_left_of_the_button() {
+        with_py(|py| {
+            let mut env = table(py, 4, (1, 2), 100, &[]);
+            let hands: [&[&str]; 4] = [&["2c", "3d"], &["2d", "3s"], &["2s", "4d"], &["3c", "4s"]];
+            let deck = rigged(&env, &hands, &["Ah", "Kd", "Qc", "Js", "Th"]);
+            env.rig_next_deal(deck).unwrap();
+            env.reset(false).unwrap();
+            let dealer = env.dealer_pos;
+            let seat = |k: usize| (dealer + k) % 4;
+
+            // Three limp in, the small blind folds: 7 chips for a straight on the board
+            act(&mut env, Action::Call, 2);
+            act(&mut env, Action::Call, 2);
+            act(&mut env, Action::Fold, 0);
+            act(&mut env, Action::Check, 0);
+            check_down(&mut env);
+
+            assert_eq!(env.stacks[seat(2)], 101);
+            assert_eq!(env.stacks[seat(3)], 100);
+            assert_eq!(env.stacks[seat(0)], 100);
+            assert_eq!(env.stacks[seat(1)], 99);
+        });
+    }
+
+    #[test]
+    fn short_deck_flush_beats_a_full_house_at_showdown() {
+        with_py(|py| {
+            let mut env = table(py, 2, (1, 2), 100, &[("short_deck", true.into_py(py))]);
+            assert_eq!(env.base_deck.len(), 36);
+            let deck = rigged(&env, &[&["Ah", "8h"], &["Kd", "9c"]], &["Kh", "9h", "7h", "Ks", "6c"]);
+            env.rig_next_deal(deck).unwrap();
+            env.reset(false).unwrap();
+            check_down(&mut env);
+            assert_eq!(env.stacks, vec![102, 98]);
+        });
+    }
+
+    #[test]
+    fn short_deck_ace_plays_low_in_a_straight() {
+        let score = |list: &[&str], short_deck: bool| {
+            let cards: Vec<String> = list.iter().map(|card| card.to_string()).collect();
+            let five: Vec<Card> = parse_hand(&cards).unwrap().iter().collect();
+            rank_score(&rank_five(&five, short_deck), short_deck)
+        };
+        let wheel = score(&["Ah", "6d", "7c", "8s", "9d"], true);
+        let trips = score(&["Ah", "Ad", "Ac", "8s", "9d"], true);
+        let six_high = score(&["6h", "7d", "8c", "9s", "Td"], true);
+        let ace_high = score(&["Ah", "6d", "7c", "8s", "Jd"], true);
+        assert!(wheel > ace_high);
+        assert!(wheel < six_high);
+        // Short deck, a straight beats three of a kind as usual
+        assert!(wheel > trips);
+        // Out of short deck the same cards are only ace high
+        assert!(score(&["Ah", "6d", "7c", "8s", "9d"], false) >> 32 == 0);
+    }
+
+    #[test]
+    fn json_round_trip_is_stable() {
+        with_py(|py| {
+            let mut env = table(py, 3, (1, 2), 100, &[("record_history", true.into_py(py))]);
+            act(&mut env, Action::Raise, 6);
+            act(&mut env, Action::Call, 6);
+            let json = env.to_json().unwrap();
+            let restored = PokerEnv::from_json(py, json.clone()).unwrap();
+            assert_eq!(restored.to_json().unwrap(), json);
+            assert_eq!(restored.deck, env.deck);
+            assert_eq!(restored.current_player, env.current_player);
+            assert!(restored.agents.iter().all(|agent| agent.is_none(py)));
+        });
+    }
+
+    #[test]
+    fn clones_play_on_without_touching_each_other() {
+        with_py(|py| {
+            let mut env = table(py, 3, (1, 2), 100, &[]);
+            act(&mut env, Action::Call, 2);
+            let before = env.to_json().unwrap();
+            let mut branch = env.clone_state().unwrap();
+
+            // One folds the rest of the way, the other calls to the flop
+            act(&mut branch, Action::Fold, 0);
+            assert!(act(&mut branch, Action::Fold, 0));
+            assert_eq!(env.to_json().unwrap(), before);
+
+            act(&mut env, Action::Call, 2);
+            act(&mut env, Action::Check, 0);
+            assert_eq!(env.current_phase, Phase::Flop);
+            assert_eq!(env.deck.len() + 3, branch.deck.len());
+            assert!(branch.community_cards.is_empty());
+            assert!(branch.hand_over() && !env.hand_over());
+        });
+    }
+
+    /// Everyone goes all in preflop, the board is dealt and the hand is left to resolve
+    fn all_in_to_showdown(env: &mut PokerEnv) {
+        while env.seek_actor().unwrap() {
+            let stack = env.stacks[env.current_player];
+            let action = match raise_range(env) {
+                Some(_) => Action::Raise,
+                None => Action::Call,
+            };
+            env.play_action(action, stack, false).unwrap();
+            if env.end_of_turn() {
+                break;
+            }
+        }
+        env.run_out_board(false).unwrap();
+    }
+
+    #[test]
+    fn resolution_returns_the_winners_of_each_pot() {
+        with_py(|py| {
+            let mut env = table(py, 3, (1, 2), 100, &[]);
+            env.stacks = vec![50, 100, 100];
+            let hands: [&[&str]; 3] = [&["Ah", "Ad"], &["Kh", "Kd"], &["Qh", "Qd"]];
+            let deck = rigged(&env, &hands, &["2c", "7s", "9d", "3c", "Js"]);
+            env.rig_next_deal(deck).unwrap();
+            env.reset(false).unwrap();
+            all_in_to_showdown(&mut env);
+
+            let names = env.names.clone();
+            let awards = env.resolution(false).unwrap();
+            assert_eq!(awards, vec![(0, vec![(names[0].clone(), 150)]), (1, vec![(names[1].clone(), 100)])]);
+            assert_eq!(env.stacks, vec![150, 100]);
+        });
+    }
+
+    #[test]
+    fn resolution_returns_the_odd_chip_with_its_winner() {
+        with_py(|py| {
+            let mut env = table(py, 3, (1, 2), 100, &[("record_history", true.into_py(py))]);
+            let hands: [&[&str]; 3] = [&["2c", "3d"], &["9c", "9d"], &["2d", "3s"]];
+            let deck = rigged(&env, &hands, &["Ah", "Kd", "Qc", "Js", "Th"]);
+            env.rig_next_deal(deck).unwrap();
+            // The button goes to seat 0
+            env.dealer_pos = 2;
+            env.reset(false).unwrap();
+            // The small blind folds his chip between two all ins
+            for (action, amount) in [(Action::Raise, 100), (Action::Fold, 0), (Action::Call, 100)] {
+                assert!(env.seek_actor().unwrap());
+                env.play_action(action, amount, false).unwrap();
+                env.end_of_turn();
+            }
+            while env.current_phase != Phase::Showdown {
+                env.advance_phase(false).unwrap();
+            }
+            let names = env.names.clone();
+            let awards = env.resolution(false).unwrap();
+
+            // The pot of 201 is split on the board, the odd chip to the big blind
+            assert_eq!(awards, vec![(0, vec![(names[2].clone(), 101), (names[0].clone(), 100)])]);
+            let awarded: Vec<(String, i32)> = env
+                .history
+                .iter()
+                .filter_map(|(_, event)| match event {
+                    Event::PotAwarded { player, amount, .. } => Some((player.clone(), *amount)),
+                    _ => None,
+                })
+                .collect();
+            assert_eq!(awarded, awards[0].1);
+        });
+    }
+
+    #[test]
+    fn run_it_twice_shares_the_pot_between_two_boards() {
+        with_py(|py| {
+            let mut env = table(py, 2, (1, 2), 100, &[("run_it_twice", true.into_py(py))]);
+            let board = ["Kc", "2s", "7d", "8h", "3c"];
+            let deck = rigged(&env, &[&["Ah", "Ad"], &["Kh", "Kd"]], &board);
+            env.rig_next_deal(deck).unwrap();
+            env.reset(false).unwrap();
+            all_in_to_showdown(&mut env);
+            let names = env.names.clone();
+            let awards = env.resolution(false).unwrap();
+
+            assert_eq!(env.community_cards, board);
+            assert_eq!(env.second_board.len(), 5);
+            assert!(env.second_board.iter().all(|card| !board.contains(&card.as_str())));
+            // One award of half the pot for each board, the first one to the set of kings
+            assert_eq!(awards.len(), 2);
+            assert_eq!(awards[0], (0, vec![(names[1].clone(), 100)]));
+            assert!(awards.iter().all(|(pot, winners)| *pot == 0 && winners.iter().map(|(_, amount)| amount).sum::<i32>() == 100));
+            assert_eq!(env.stacks.iter().sum::<i32>(), 200);
+        });
+    }
+
+    #[test]
+    fn short_all_in_does_not_use_up_a_raise_of_the_cap() {
+        with_py(|py| {
+            let mut env = table(py, 4, (1, 2), 100, &[("max_raises_per_street", 2.into_py(py))]);
+            act(&mut env, Action::Raise, 10);
+            // The next player shoves 4 more, short of a full raise: one raise of the cap is left
+            let shover = env.current_player;
+            env.stacks[shover] = 14;
+            act(&mut env, Action::Raise, 14);
+            assert_eq!(env.raises_this_street, 1);
+            assert_eq!(raise_range(&mut env).map(|(min, _)| min), Some(22));
+            act(&mut env, Action::Raise, 30);
+            // Capped now, calling stays possible
+            assert_eq!(raise_range(&mut env), None);
+            assert!(env.legal_actions().contains(&LegalAction::Call(30)));
+        });
+    }
+
+    #[test]
+    fn pinned_button_keeps_the_blinds_in_place() {
+        with_py(|py| {
+            let options = [("dealer_pos", 2.into_py(py)), ("rotate_button", false.into_py(py))];
+            let mut env = table(py, 4, (1, 2), 100, &options);
+            for _ in 0..3 {
+                env.reset(false).unwrap();
+                assert_eq!(env.dealer_pos, 2);
+                assert_eq!(env.bets, vec![2, 0, 0, 1]);
+                assert_eq!(env.current_player, 1);
+                fold_out(&mut env);
+            }
+        });
+    }
+
+    #[test]
+    fn rotating_button_starts_at_the_seat_asked() {
+        with_py(|py| {
+            // The first hand is dealt by the constructor
+            let mut env = table(py, 4, (1, 2), 100, &[("dealer_pos", 2.into_py(py))]);
+            assert_eq!(env.dealer_pos, 2);
+            fold_out(&mut env);
+            env.reset(false).unwrap();
+            assert_eq!(env.dealer_pos, 3);
+            assert_eq!(env.bets, vec![1, 2, 0, 0]);
+        });
+    }
+
+    #[test]
+    fn log_callback_gets_the_events_of_a_verbose_game() {
+        with_py(|py| {
+            let globals = PyDict::new_bound(py);
+            let code = "log = []\nclass Caller:\n    def choose_action(self, state, actions):\n        return next(a for a in actions if a[0] in ('check', 'call'))\n";
+            py.run_bound(code, Some(&globals), None).unwrap();
+            let callback = py.eval_bound("lambda level, event: log.append((level, event))", Some(&globals), None).unwrap();
+            let agents = py.eval_bound("[Caller(), Caller()]", Some(&globals), None).unwrap().extract().unwrap();
+
+            let mut env = seated(py, agents, (1, 2), 10, &[("log_callback", callback.into_py(py))]);
+            env.play_game(py, 1, true, Some(1), None).unwrap();
+
+            let events: Vec<(String, Bound<'_, PyDict>)> = globals.get_item("log").unwrap().unwrap().extract().unwrap();
+            let kind = |event: &Bound<'_, PyDict>| event.get_item("type").unwrap().unwrap().extract::<String>().unwrap();
+            assert!(events.iter().any(|(level, event)| level == "info" && kind(event) == "player_action"));
+            assert!(events.iter().any(|(level, event)| level == "info" && kind(event) == "pot_awarded"));
+            assert!(events.iter().any(|(level, event)| level == "debug" && kind(event) == "stacks"));
+        });
+    }
+
+    #[test]
+    fn small_blind_short_of_his_blind_wins_only_the_main_pot() {
+        with_py(|py| {
+            let options = [("dealer_pos", 0.into_py(py)), ("rotate_button", false.into_py(py))];
+            let mut env = table(py, 3, (10, 20), 100, &options);
+            env.stacks = vec![100, 5, 100];
+            let hands: [&[&str]; 3] = [&["Kh", "Kd"], &["Ah", "Ad"], &["Qh", "Qd"]];
+            let deck = rigged(&env, &hands, &["2c", "7s", "9d", "3c", "Js"]);
+            env.rig_next_deal(deck).unwrap();
+            env.reset(false).unwrap();
+            assert_eq!(env.bets, vec![0, 5, 20]);
+            assert!(env.all_in[1]);
+            assert_eq!(raise_range(&mut env).map(|(min, _)| min), Some(40));
 
-        // Always fold
-        Python::with_gil(|py| {
-            actions.push(PyTuple::new_bound(py, [Action::Fold.to_object(py)]).into());
+            // The short blind is only in the first 15 chips, the rest is between the others
+            check_down(&mut env);
+            assert_eq!(env.stacks, vec![110, 15, 80]);
         });
+    }
 
-        let sum_all_in: usize = self.all_in.iter().map(|&b| b as usize).sum();
-        let sum_folded: usize = self.folded.iter().map(|&b| b as usize).sum();
+    #[test]
+    fn walk_gives_the_blinds_to_the_big_blind() {
+        with_py(|py| {
+            let mut env = table(py, 4, (1, 2), 100, &[("record_history", true.into_py(py))]);
+            env.reset(false).unwrap();
+            let (small_blind, big_blind) = (env.sb_pos(), env.bb_pos());
+            fold_out(&mut env);
 
-        if sum_all_in + sum_folded == self.folded.len() - 1 {
-            if current_bet != max_bet {
-                let call_amount = max_bet.min(current_stack);
-                Python::with_gil(|py| {
-                    actions.push(PyTuple::new_bound(py, [Action::Call.to_object(py), call_amount.to_object(py)]).into());
-                });
+            assert!(env.community_cards.is_empty());
+            for seat in 0..4 {
+                let expected = match seat {
+                    s if s == big_blind => 101,
+                    s if s == small_blind => 99,
+                    _ => 100,
+                };
+                assert_eq!(env.stacks[seat], expected);
             }
-            return Ok(actions)
-        };
-
-        // "Check" is the bet of the player is equal to the max_bet, "Call" if not
-        if current_bet == max_bet {
-            Python::with_gil(|py| {
-                actions.push(PyTuple::new_bound(py, [Action::Check.to_object(py)]).into());
-            });
-        } else {
-            let call_amount = max_bet.min(current_stack);
-            Python::with_gil(|py| {
-                actions.push(PyTuple::new_bound(py, [Action::Call.to_object(py), call_amount.to_object(py)]).into());
-            });
-        };
+            let walk = Event::Walk { player: env.names[big_blind].clone() };
+            assert_eq!(env.history.iter().filter(|(_, event)| *event == walk).count(), 1);
+        });
+    }
 
-        if current_stack > max_bet {
-            let raise_range: (i32, i32);
-            if current_stack >= max_bet*2 {
-                raise_range = (max_bet + self.max_raise, current_stack);
-            } else {
-                raise_range = (current_stack, current_stack);
+    #[test]
+    fn vec_env_plays_its_own_copies() {
+        with_py(|py| {
+            let env = table(py, 2, (1, 2), 100, &[]);
+            let mut batch = VecPokerEnv::new(vec![env.clone(), env.clone()]).unwrap();
+            let fold = PyTuple::new_bound(py, ["fold".into_py(py), 0i32.into_py(py)]).unbind();
+            batch.step_all(py, vec![fold.clone_ref(py), fold]).unwrap();
+            for played in batch.envs() {
+                assert!(played.hand_over());
             }
-            Python::with_gil(|py| {
-                actions.push(PyTuple::new_bound(py, [Action::Raise.to_object(py), raise_range.to_object(py)]).into());
-            });
-        };
+            assert!(!env.hand_over());
+        });
+    }
 
-        Ok(actions)
+    #[test]
+    fn losers_muck_only_when_asked() {
+        with_py(|py| {
+            let shown = |muck: bool| {
+                let options = [("muck_losers", muck.into_py(py)), ("record_history", true.into_py(py))];
+                let mut env = table(py, 2, (1, 2), 100, &options);
+                let deck = rigged(&env, &[&["Ah", "3h"], &["Kd", "9c"]], &["Kh", "9h", "4h", "Ks", "2c"]);
+                env.rig_next_deal(deck).unwrap();
+                env.reset(false).unwrap();
+                check_down(&mut env);
+                let events = env.history.iter().filter(|(_, event)| matches!(event, Event::HandShown { .. })).count();
+                assert_eq!(events, env.shown_hands.len());
+                (env.shown_hands.clone(), env.names.clone())
+            };
+
+            let (all, names) = shown(false);
+            assert_eq!(all, vec![(names[0].clone(), vec!["Ah".to_string(), "3h".to_string()]), (names[1].clone(), vec!["Kd".to_string(), "9c".to_string()])]);
+            // The flush of seat 0 loses the showdown and is mucked
+            let (winners, names) = shown(true);
+            assert_eq!(winners, vec![(names[1].clone(), vec!["Kd".to_string(), "9c".to_string()])]);
+        });
     }
 
-    /// Return observable state of game from the POV of the current player
-    pub fn get_state(&mut self) -> PyResult<Py<PyDict>> {
-        Python::with_gil(|py| {
-            let dict = PyDict::new_bound(py);
-            dict.set_item("player_cards", self.player_cards[self.current_player].clone())?;
-            dict.set_item("community_cards", self.community_cards.clone())?;
-            dict.set_item("stacks", self.stacks.clone())?;
-            dict.set_item("bets", self.bets.clone())?;
-            dict.set_item("phase", &self.current_phase)?;
-            dict.set_item("current_player", self.current_player)?;
-            dict.set_item("folded", self.folded.clone())?;
-            dict.set_item("all_in", self.all_in.clone())?;
-            Ok(dict.into())
-        })
+    /// Check or call for the current player, return whether the hand is over
+    fn check_or_call(env: &mut PokerEnv) -> bool {
+        let action = passive(env);
+        env.step(action).unwrap().2
     }
 
-    /// Print overall state
-    pub fn overall_state(&mut self) -> PyResult<()> {
-        println!("phase: {0:?}\nplayers_cards: {1:?}\ncommunity_cards: {2:?}\nfolded: {3:?}')\nall_in: {4:?}\nstacks: {5:?}\nbets: {6:?}\n",
-                    self.current_phase,
-                    self.player_cards,
-                    self.community_cards,
-                    self.folded,
-                    self.all_in,
-                    self.stacks,
-                    self.bets);
-        Ok(())
+    #[test]
+    fn checks_around_close_the_street_after_one_turn_each() {
+        with_py(|py| {
+            let mut env = table(py, 3, (1, 2), 100, &[]);
+            env.reset(false).unwrap();
+            let (button, sb, bb) = (env.dealer_pos, (env.dealer_pos + 1) % 3, (env.dealer_pos + 2) % 3);
+            check_or_call(&mut env);
+            check_or_call(&mut env);
+            // The big blind keeps his option once called
+            assert_eq!((env.current_phase.clone(), env.current_player), (Phase::Preflop, bb));
+            act(&mut env, Action::Check, 0);
+            assert_eq!((env.current_phase.clone(), env.current_player), (Phase::Flop, sb));
+
+            act(&mut env, Action::Check, 0);
+            act(&mut env, Action::Check, 0);
+            assert_eq!((env.current_phase.clone(), env.current_player), (Phase::Flop, button));
+            act(&mut env, Action::Check, 0);
+            assert_eq!((env.current_phase.clone(), env.current_player), (Phase::Turn, sb));
+        });
     }
 
-    /// Proceed 1 turn of bet
-    pub fn step_bid(&mut self, verbose: bool) -> PyResult<()> {
-        let mut last_bet = (self.current_player + self.num_players - 1) % self.num_players;
-        loop {
-            if self.folded[self.current_player] {
-                if last_bet == self.current_player {
-                    break;
-                }
-                self.current_player = (self.current_player + 1) % self.num_players;
-                continue;
-            }
+    /// Call and check the preflop round
+    fn check_to_flop(env: &mut PokerEnv) {
+        while env.current_phase == Phase::Preflop {
+            check_or_call(env);
+        }
+    }
 
-            let agent = self.agents[self.current_player].clone();
-            let state = self.get_state()?;
-            let available_actions = self.get_available_actions()?;
+    #[test]
+    fn flop_check_raise_gives_a_new_turn_to_the_bettor_and_the_caller() {
+        with_py(|py| {
+            let mut env = table(py, 3, (1, 2), 100, &[]);
+            env.reset(false).unwrap();
+            let (button, sb, bb) = (env.dealer_pos, (env.dealer_pos + 1) % 3, (env.dealer_pos + 2) % 3);
+            check_to_flop(&mut env);
+            act(&mut env, Action::Check, 0);
+            act(&mut env, Action::Raise, 4);
+            check_or_call(&mut env);
+            // The check-raise reopens the street for both
+            act(&mut env, Action::Raise, 12);
+            assert_eq!((env.current_phase.clone(), env.current_player), (Phase::Flop, bb));
+            check_or_call(&mut env);
+            assert_eq!((env.current_phase.clone(), env.current_player), (Phase::Flop, button));
+            check_or_call(&mut env);
+            assert_eq!((env.current_phase.clone(), env.current_player), (Phase::Turn, sb));
+            assert_eq!(env.pot_size(), 42);
+        });
+    }
 
-            if available_actions.len() == 1 {
-                break;
-            }
+    #[test]
+    fn re_raise_reopens_the_action_of_the_raiser() {
+        with_py(|py| {
+            let mut env = table(py, 3, (1, 2), 100, &[]);
+            env.reset(false).unwrap();
+            let (button, sb) = (env.dealer_pos, (env.dealer_pos + 1) % 3);
+            act(&mut env, Action::Raise, 6);
+            act(&mut env, Action::Raise, 18);
+            act(&mut env, Action::Fold, 0);
+            assert_eq!((env.current_phase.clone(), env.current_player), (Phase::Preflop, button));
+            assert_eq!(raise_range(&mut env), Some((30, 100)));
+            check_or_call(&mut env);
+            assert_eq!((env.current_phase.clone(), env.current_player), (Phase::Flop, sb));
+            assert_eq!(env.pot_size(), 38);
+        });
+    }
 
-            if !available_actions.is_empty() {
-                // Call agent's choose_action method
-                let action = Python::with_gil(|py| {
-                    agent.call_method1(py, "choose_action", (state, available_actions))
-                })?;
+    #[test]
+    fn replaced_cards_go_back_under_the_deck() {
+        with_py(|py| {
+            let mut env = table(py, 2, (1, 2), 100, &[]);
+            let deck = rigged(&env, &[&["Ah", "Kh"], &["2c", "7d"]], &["Qs", "Js", "Ts"]);
+            env.rig_next_deal(deck).unwrap();
+            env.reset(false).unwrap();
+            let cards = |v: &[&str]| v.iter().map(|c| c.to_string()).collect::<Vec<_>>();
 
-                if verbose {
-                    println!("{} has {}", self.names[self.current_player], action)
-                }
+            env.set_player_cards(vec![cards(&["As", "Ks"]), cards(&["2c", "7d"])]).unwrap();
+            assert_eq!(env.deck[..2], cards(&["Ah", "Kh"]));
+            assert!(!env.deck.contains(&"As".to_string()));
+            env.set_community_cards(cards(&["Qs", "Js", "Ts"])).unwrap();
+            env.set_community_cards(cards(&["9s", "8s", "Ts"])).unwrap();
+            assert_eq!(env.deck[..2], cards(&["Qs", "Js"]));
+            assert_eq!(env.deck.len() + 7, env.base_deck.len());
 
-                // Extract the first element of the action tuple
-                let action_type = Python::with_gil(|py| {
-                    action
-                        .bind(py)
-                        .get_item(0)?
-                        .extract::<String>()
-                })?;
+            // Reordering keeps the cards of the deck, dropping one is refused
+            let mut reversed = env.deck.clone();
+            reversed.reverse();
+            env.set_deck(reversed.clone()).unwrap();
+            assert_eq!(env.deck, reversed);
+            reversed.pop();
+            assert!(env.set_deck(reversed).is_err());
+        });
+    }
 
-                match action_type.as_str() {
-                    "fold" => {
-                        self.folded[self.current_player] = true;
-                    }
-                    "check" => {}
-                    "call" => {
-                        let amount = Python::with_gil(|py| {
-                            action.bind(py).get_item(1)?.extract::<i32>()
-                        })?;
-                        self.apply_bet(self.current_player, amount)?;
-                    }
-                    "raise" => {
-                        let amount = Python::with_gil(|py| {
-                            action.bind(py).get_item(1)?.extract::<i32>()
-                        })?;
-                        let raise_amount = amount - self.bets.iter().max().copied().unwrap_or(0);
-                        if raise_amount > self.max_raise {
-                            self.max_raise = raise_amount;
-                        }
-                        self.apply_bet(self.current_player, amount)?;
-                        last_bet = (self.current_player + self.num_players - 1) % self.num_players;
-                    }
-                    _ => {
-                        return Err(PyErr::new::<pyo3::exceptions::PyValueError, _>(
-                            "Error: not valid action",
-                        ));
-                    }
+    #[test]
+    fn heads_up_big_blind_acts_first_after_the_flop() {
+        with_py(|py| {
+            let mut env = table(py, 2, (1, 2), 100, &[]);
+            for _ in 0..2 {
+                env.reset(false).unwrap();
+                let (button, big_blind) = (env.dealer_pos, 1 - env.dealer_pos);
+                assert_eq!(env.current_player, button);
+                check_or_call(&mut env);
+                act(&mut env, Action::Check, 0);
+                for phase in [Phase::Flop, Phase::Turn, Phase::River] {
+                    assert_eq!((env.current_phase.clone(), env.current_player), (phase, big_blind));
+                    act(&mut env, Action::Check, 0);
+                    assert_eq!(env.current_player, button);
+                    act(&mut env, Action::Check, 0);
                 }
+                assert!(env.hand_over());
             }
+        });
+    }
 
-            let sum_folded: usize = self.folded.iter().map(|&b| b as usize).sum();
-            if sum_folded == self.folded.len() - 1 {
-                break;
-            }
-
-            if last_bet == self.current_player {
-                break;
-            }
+    #[test]
+    fn replay_reproduces_the_recorded_hand() {
+        with_py(|py| {
+            let mut env = table(py, 3, (1, 2), 100, &[]);
+            let deck: Vec<String> = env.base_deck.iter().rev().cloned().collect();
+            env.rig_next_deal(deck.clone()).unwrap();
+            env.reset(false).unwrap();
+            let dealer = env.dealer_pos;
+            act(&mut env, Action::Raise, 6);
+            check_or_call(&mut env);
+            act(&mut env, Action::Fold, 0);
+            act(&mut env, Action::Raise, 10);
+            check_or_call(&mut env);
+            check_down(&mut env);
+            let actions = env.hand_actions.clone();
 
-            self.current_player = (self.current_player + 1) % self.num_players;
-        }
+            let mut again = table(py, 3, (1, 2), 100, &[]);
+            assert!(again.replay(deck.clone(), actions.clone(), Some(dealer)).unwrap());
+            assert_eq!(again.community_cards, env.community_cards);
+            assert_eq!(again.player_cards, env.player_cards);
+            assert_eq!(again.rewards, env.rewards);
+            assert_eq!(again.stacks, env.stacks);
 
-        Ok(())
+            // A check facing the raise is refused
+            let mut wrong = actions.clone();
+            let raise = wrong.iter().position(|(_, name, _)| name == "raise").unwrap();
+            wrong[raise + 1].1 = "check".to_string();
+            let mut broken = table(py, 3, (1, 2), 100, &[]);
+            assert!(broken.replay(deck, wrong, Some(dealer)).is_err());
+        });
     }
 
-    /// Advance to the next phase of the game
-    pub fn advance_phase(&mut self, verbose: bool) -> PyResult<()> {
-        if verbose {
-            println!("End of {:?}", self.current_phase);
-        }
-
-        match self.current_phase {
-            Phase::Preflop => {
-                self.current_player = (self.dealer_pos + 1) % self.num_players;
-                self.community_cards = (0..3)
-                    .map(|_| self.deck.pop().ok_or_else(|| PyErr::new::<pyo3::exceptions::PyValueError, _>("Deck is empty")))
-                    .collect::<PyResult<Vec<_>>>()?;
-                self.current_phase = Phase::Flop;
-            }
-            Phase::Flop => {
-                self.current_player = (self.dealer_pos + 1) % self.num_players;
-                let card = self.deck.pop().ok_or_else(|| PyErr::new::<pyo3::exceptions::PyValueError, _>("Deck is empty"))?;
-                self.community_cards.push(card);
-                self.current_phase = Phase::Turn;
-            }
-            Phase::Turn => {
-                self.current_player = (self.dealer_pos + 1) % self.num_players;
-                let card = self.deck.pop().ok_or_else(|| PyErr::new::<pyo3::exceptions::PyValueError, _>("Deck is empty"))?;
-                self.community_cards.push(card);
-                self.current_phase = Phase::River;
+    #[test]
+    fn call_of_the_rest_of_the_stack_on_the_turn_is_all_in() {
+        with_py(|py| {
+            let mut env = table(py, 3, (1, 2), 100, &[]);
+            env.stacks = vec![100, 40, 100];
+            env.reset(false).unwrap();
+            let short = 1;
+            act(&mut env, Action::Raise, 10);
+            check_to_flop(&mut env);
+            while env.current_phase == Phase::Flop {
+                act(&mut env, Action::Check, 0);
             }
-            Phase::River => {
-                self.current_phase = Phase::Showdown;
+
+            // A bet of 50 on the turn, the short stack calls his last 30
+            while env.current_player == short {
+                act(&mut env, Action::Check, 0);
             }
-            _ => {
-                return Err(PyErr::new::<pyo3::exceptions::PyValueError, _>("Error of phase"));
+            act(&mut env, Action::Raise, 50);
+            while env.current_player != short {
+                check_or_call(&mut env);
             }
-        }
+            assert!(env.legal_actions().contains(&LegalAction::Call(30)));
+            check_or_call(&mut env);
+            assert!(env.all_in[short]);
+            assert_eq!(env.contributions[short] + env.bets[short], 40);
 
-        Ok(())
+            check_down(&mut env);
+            assert_eq!(env.stacks.iter().sum::<i32>(), 240);
+        });
     }
 
-    /// Kill a player (when he has no stack left)
-    pub fn kill(&mut self, player: usize) -> PyResult<()> { 
-        self.stacks.remove(player);
-        self.bets.remove(player);
-        self.dead_agents.push(self.agents.remove(player));
-        self.dead_names.push(self.names.remove(player));
-        self.folded.remove(player);
-        self.all_in.remove(player);
-        self.rewards.remove(player);
-        self.player_cards.remove(player);
-        self.num_players -= 1;
-        Ok(())
+    #[test]
+    fn short_all_in_does_not_reopen_the_raise() {
+        with_py(|py| {
+            let options = [("dealer_pos", 0.into_py(py)), ("rotate_button", false.into_py(py))];
+            let mut env = table(py, 3, (1, 2), 100, &options);
+            env.stacks = vec![100, 14, 100];
+            env.reset(false).unwrap();
+            assert_eq!(env.current_player, 0);
+            act(&mut env, Action::Raise, 10);
+            // All in to 14, a raise of 4 short of the 8 of the raise: the raiser may only call to 14
+            act(&mut env, Action::AllIn, 0);
+            assert!(env.all_in[1]);
+            // The big blind has not acted yet and may still raise
+            assert_eq!(env.current_player, 2);
+            assert!(raise_range(&mut env).is_some());
+            check_or_call(&mut env);
+
+            assert_eq!(env.current_player, 0);
+            assert_eq!(env.legal_actions(), vec![LegalAction::Fold, LegalAction::Call(14)]);
+            check_or_call(&mut env);
+            assert_eq!(env.current_phase, Phase::Flop);
+        });
     }
 
-    /// Determine winner(s) and conclude a game
-    pub fn resolution(&mut self, verbose: bool) -> PyResult<()> {
-        let mut scores: Vec<(String, Rank)> = Vec::new();
-        let stacks_before_resolution = self.stacks.iter().sum::<i32>();
+    #[test]
+    fn simulate_batch_deals_every_game_apart() {
+        with_py(|py| {
+            let wins = |seed: u64| {
+                let mut env = table(py, 2, (1, 2), 100, &[("seed", seed.into_py(py))]);
+                let stats = env.simulate_batch(py, 1000, vec![NativeStrategy::AlwaysCall, NativeStrategy::AlwaysCall]).unwrap();
+                stats.bind(py).get_item("wins").unwrap().unwrap().extract::<Vec<usize>>().unwrap()
+            };
+            let first = wins(7);
+            // Calling down every hand, each seat wins close to half of them, ties apart
+            assert!(first.iter().all(|&won| (400..600).contains(&won)), "{:?}", first);
+            assert_eq!(wins(7), first);
+            assert_ne!(wins(8), first);
+        });
+    }
 
-        let board = self.community_cards.join("");
+    #[test]
+    fn same_seed_plays_the_same_game_again() {
+        with_py(|py| {
+            let game = |seed: u64| {
+                let options = [("seed", seed.into_py(py)), ("record_history", true.into_py(py))];
+                let mut env = table(py, 3, (1, 2), 100, &options);
+                let strategies = vec![NativeStrategy::Random; 3];
+                let stats = env.simulate_batch(py, 20, strategies).unwrap().bind(py).repr().unwrap().to_string();
+                let equities = env.showdown_equities(50).unwrap().bind(py).repr().unwrap().to_string();
+                (stats, equities, env.to_json().unwrap())
+            };
+            assert_eq!(game(7), game(7));
+            assert_ne!(game(7).0, game(8).0);
+        });
+    }
 
-        for i in 0..self.num_players {
-            if !self.folded[i] {
-                let player_cards = self.player_cards[i].clone().join("");
-                let hand = Hand::new_from_str(&format!("{}{}", board, player_cards)).unwrap();
-                let rank = hand.rank();
-                scores.push((self.names[i].clone(), rank));
-            }
+    #[test]
+    fn allin_ev_rewards_credit_the_equity_and_pay_the_real_pot() {
+        with_py(|py| {
+            let options = [("allin_ev_rewards", true.into_py(py)), ("equity_iterations", 4000.into_py(py)), ("seed", 3.into_py(py))];
+            let mut env = table(py, 2, (1, 2), 100, &options);
+            // The kings hit a set on the river against the aces
+            let deck = rigged(&env, &[&["Ah", "Ad"], &["Kh", "Kd"]], &["2c", "7s", "9d", "3c", "Ks"]);
+            env.rig_next_deal(deck).unwrap();
+            env.reset(false).unwrap();
+            let kings = env.names[1].clone();
+            act(&mut env, Action::AllIn, 0);
+            assert!(check_or_call(&mut env));
+
+            // The aces are eliminated all the same
+            assert_eq!((env.names.clone(), env.stacks.clone()), (vec![kings], vec![200]));
+            // The aces were worth about 82% of the pot of 200
+            assert!((50..75).contains(&env.rewards[0]), "{:?}", env.rewards);
+            assert_eq!(env.rewards.iter().sum::<i32>(), 0);
+        });
+    }
+
+    /// Bust `target` from a five-handed hand dealt with the button on seat 0, and return the
+    /// names of the table, then of the dealer, the small blind if he is not dead and the big
+    /// blind of the next hand
+    fn bust_and_deal(py: Python, target: usize) -> (Vec<String>, (String, Option<String>, String)) {
+        let mut env = table(py, 5, (1, 2), 100, &[]);
+        env.stacks[target] = 10;
+        let mut hands: Vec<&[&str]> = vec![&["3c", "3d"], &["4c", "4d"], &["5c", "5d"], &["6c", "6d"], &["Jc", "Jd"]];
+        hands[target] = &["7c", "2d"];
+        let deck = rigged(&env, &hands, &["As", "Ks", "Qd", "9h", "8h"]);
+        env.rig_next_deal(deck).unwrap();
+        env.dealer_pos = 4;
+        env.reset(false).unwrap();
+        assert_eq!(env.dealer_pos, 0);
+        let names = env.names.clone();
+
+        // Everyone limps up to the all in of the target, the first to act after him calls it
+        let mut caller = None;
+        while !env.hand_over() {
+            let seat = env.current_player;
+            let action = match (seat == target, env.all_in[target]) {
+                (true, _) => Action::AllIn,
+                (false, false) if env.legal_actions().contains(&LegalAction::Check) => Action::Check,
+                (false, false) => Action::Call,
+                (false, true) if *caller.get_or_insert(seat) == seat => Action::Call,
+                (false, true) => Action::Fold,
+            };
+            match action {
+                Action::Call => check_or_call(&mut env),
+                action => act(&mut env, action, 0),
+            };
         }
+        assert!(!env.names.contains(&names[target]));
 
-        scores.sort_by_key(|x| Reverse(x.1));
+        env.reset(false).unwrap();
+        let small_blind = (!env.dead_small_blind).then(|| env.names[env.sb_pos()].clone());
+        (names, (env.names[env.dealer_pos].clone(), small_blind, env.names[env.bb_pos()].clone()))
+    }
 
-        let mut pots = vec![0];
-        let mut pots_names: Vec<Vec<String>> = vec![vec![]];
+    #[test]
+    fn small_blind_busted_the_big_blind_posts_it_next() {
+        with_py(|py| {
+            let (names, next) = bust_and_deal(py, 1);
+            assert_eq!(next, (names[0].clone(), Some(names[2].clone()), names[3].clone()));
+        });
+    }
 
-        let sum_all_in: usize = self.all_in.iter().map(|&b| b as usize).sum();
-        if sum_all_in == 0 {
-            for i in 0..self.num_players {
-                pots[0] += self.bets[i];
+    #[test]
+    fn big_blind_busted_the_next_small_blind_is_dead() {
+        with_py(|py| {
+            let (names, next) = bust_and_deal(py, 2);
+            assert_eq!(next, (names[1].clone(), None, names[3].clone()));
+        });
+    }
 
-                if !self.folded[i] {
-                    pots_names[0].push(self.names[i].clone())
-                }
-            }
-        } else {
-            let mut pot_index = 0;
-            let mut bets = self.bets.clone();
+    #[test]
+    fn button_busted_the_blinds_move_on_by_one() {
+        with_py(|py| {
+            let (names, next) = bust_and_deal(py, 0);
+            assert_eq!(next, (names[1].clone(), Some(names[2].clone()), names[3].clone()));
+        });
+    }
 
-            loop {
-                let min = bets.iter()
-                    .zip(self.folded.iter())
-                    .enumerate()
-                    .filter_map(|(_i, (&num, &flag))| {
-                        if num != 0 && !flag {
-                            Some(num)
-                        } else {
-                            None
-                        }
-                    })
-                    .min();
+    #[test]
+    fn run_out_board_deals_the_five_cards_after_an_all_in_preflop() {
+        with_py(|py| {
+            let mut env = table(py, 2, (1, 2), 100, &[("burn_cards", true.into_py(py))]);
+            env.reset(false).unwrap();
+            // Not while the betting is open
+            assert!(env.run_out_board(false).is_err());
 
-                if let Some(val) = min {
-                    for i in 0..self.num_players {
-                        let n = std::cmp::min(val, bets[i]);
-                        if n != 0 {
-                            bets[i] -= n;
-                            pots[pot_index] += n;
+            all_in_to_showdown(&mut env);
+            assert_eq!(env.current_phase, Phase::Showdown);
+            assert_eq!((env.community_cards.len(), env.burned.len()), (5, 3));
+            env.resolution(false).unwrap();
+            assert_eq!(env.stacks.iter().sum::<i32>(), 200);
+            assert_eq!(env.rewards.iter().sum::<i32>(), 0);
+        });
+    }
 
-                            if !self.folded[i] {
-                                pots_names[pot_index].push(self.names[i].clone());
-                            }
-                        }
-                    }
-                    pots.push(0);
-                    pots_names.push(Vec::new());
-                    pot_index += 1;
-                } else {
-                    break;
+    #[test]
+    fn big_blind_ante_short_of_its_stack_is_dead_money_of_the_main_pot() {
+        with_py(|py| {
+            let table_with = |stacks: Vec<i32>| {
+                let options = [
+                    ("blind_schedule", vec![(0u32, 10, 20, 20)].into_py(py)),
+                    ("ante_mode", AnteMode::BigBlindAnte.into_py(py)),
+                    ("dealer_pos", 0.into_py(py)),
+                    ("rotate_button", false.into_py(py)),
+                ];
+                let mut env = table(py, 3, (10, 20), 100, &options);
+                // The reset gives back what the blinds and the ante of the first deal put in
+                for (i, stack) in stacks.into_iter().enumerate() {
+                    env.stacks[i] = stack - env.contributions[i];
                 }
-            }
-        }
+                env.reset(false).unwrap();
+                env
+            };
 
-        if verbose {
-            println!("pots: {:?}\npots_player: {:?}", pots, pots_names);
-        }
+            // The big blind posts the blind, then the ante for the table
+            let mut env = table_with(vec![100, 100, 100]);
+            assert_eq!((env.bets[2], env.contributions[2], env.pot_size()), (20, 20, 50));
+            assert_eq!(raise_range(&mut env).unwrap().0, 40);
 
-        // Distribute the pots
-        let mut rest = 0;
-        let mut i = 0;
-        for p in pots {
+            // With 30 chips, 20 of blind and 10 of ante leave him all in
+            let mut env = table_with(vec![100, 100, 30]);
+            assert_eq!((env.bets[2], env.contributions[2]), (20, 10));
+            assert!(env.all_in[2]);
+            act(&mut env, Action::Raise, 60);
+            check_or_call(&mut env);
+            check_down(&mut env);
+            assert_eq!(env.stacks.iter().sum::<i32>(), 230);
+        });
+    }
 
-            if p == 0 {
-                continue;
+    #[test]
+    fn table_of_always_call_agents_plays_a_hand_to_the_showdown() {
+        with_py(|py| {
+            let mut agents = vec![NativeStrategy::AlwaysCall.into_py(py); 3];
+            agents.push(NativeAgent::new(NativeStrategy::AlwaysCall, SHOVE_THRESHOLD).into_py(py));
+            let mut env = seated(py, agents, (1, 2), 100, &[("record_history", true.into_py(py))]);
+            env.reset(false).unwrap();
+            // The streets as `play_game` deals them, every decision taken by the agents
+            while env.current_phase != Phase::Showdown {
+                env.step_bid(false).unwrap();
+                env.advance_phase(false).unwrap();
             }
+            env.resolution(false).unwrap();
 
-            // Determine pot winner(s)
-            let mut winners = Vec::new();
-            let mut rank: Option<Rank> = None;
-            for (name, r) in scores.clone() {
-                if pots_names[i].contains(&name) {
-                    if winners.len() == 0 {
-                        winners.push(name);
-                        rank = Some(r);
-                    } else {
-                        if Some(r) == rank {
-                            winners.push(name);
-                        } else {
-                            break;
-                        }
-                    }
-                }
+            assert_eq!(env.current_phase, Phase::Showdown);
+            assert_eq!(env.community_cards.len(), 5);
+            assert_eq!(env.stacks.iter().sum::<i32>(), 400);
+            assert_eq!(env.rewards.iter().sum::<i32>(), 0);
+            // Everyone limped and checked down, nobody folded
+            assert!(env.folded.iter().all(|&folded| !folded));
+            assert!(env.history.iter().any(|(_, event)| matches!(event, Event::HandShown { .. })));
+        });
+    }
+
+    #[test]
+    fn compute_pots_splits_the_side_pots_before_the_resolution() {
+        with_py(|py| {
+            let options = [
+                ("dealer_pos", 0.into_py(py)),
+                ("rotate_button", false.into_py(py)),
+            ];
+            let mut env = table(py, 4, (1, 2), 100, &options);
+            // The reset gives back the blinds of the first deal
+            for (i, stack) in [20, 50, 100, 100].into_iter().enumerate() {
+                env.stacks[i] = stack - env.contributions[i];
             }
+            env.reset(false).unwrap();
+            let names = env.names.clone();
+            let players = |seats: &[usize]| seats.iter().map(|&seat| names[seat].clone()).collect::<Vec<_>>();
+            assert_eq!(env.compute_pots(), vec![(3, players(&[0, 1, 2, 3]))]);
 
-            // Distribute gains
-            rest += p % (winners.len() as i32);
-            let takes = p / (winners.len() as i32);
+            // Seat 3 shoves, the two short stacks call all in and the big blind folds his 2, the
+            // pots being looked at as the betting closes
+            for action in [Action::AllIn, Action::AllIn, Action::AllIn, Action::Fold] {
+                assert!(env.seek_actor().unwrap());
+                env.play_action(action, 0, false).unwrap();
+            }
+            assert!(!env.seek_actor().unwrap());
+            assert_eq!(
+                env.compute_pots(),
+                vec![(62, players(&[0, 1, 3])), (60, players(&[1, 3])), (50, players(&[3]))]
+            );
+        });
+    }
 
-            for j in 0..self.num_players {
-                let agent_name = self.names[j as usize].clone();
-                if winners.contains(&agent_name) {
-                    self.stacks[j as usize] += takes;
-                    if verbose {
-                        println!("Winner pot {}: {}", i, agent_name);
-                    }
-                }
+    #[test]
+    fn from_config_applies_every_option() {
+        with_py(|py| {
+            let names: Vec<String> = ["ann", "bob", "cid"].iter().map(|name| name.to_string()).collect();
+            let options: Vec<(&str, PyObject)> = vec![
+                ("small_blind", 5.into_py(py)),
+                ("big_blind", 10.into_py(py)),
+                ("initial_stack", 500.into_py(py)),
+                ("betting_mode", BettingMode::PotLimit.into_py(py)),
+                ("raise_cap", 3.into_py(py)),
+                ("hole_cards", 2.into_py(py)),
+                ("short_deck", true.into_py(py)),
+                ("record_history", true.into_py(py)),
+                ("encoding", Encoding::OneHot.into_py(py)),
+                ("names", names.clone().into_py(py)),
+                ("blind_schedule", vec![(0u32, 5, 10, 2), (10, 10, 20, 4)].into_py(py)),
+                ("run_it_twice", true.into_py(py)),
+                ("max_raises_per_street", 2.into_py(py)),
+                ("dealer_pos", 1.into_py(py)),
+                ("rotate_button", false.into_py(py)),
+                ("log_callback", py.None()),
+                ("rake_percent", 5.0.into_py(py)),
+                ("rake_cap", 3.into_py(py)),
+                ("burn_cards", true.into_py(py)),
+                ("agent_method", "act".into_py(py)),
+                ("payouts", vec![0.7, 0.3].into_py(py)),
+                ("muck_losers", true.into_py(py)),
+                ("on_hand_start", py.None()),
+                ("on_phase_change", py.None()),
+                ("board_schedule", vec![3usize, 1, 1].into_py(py)),
+                ("reward_mode", RewardMode::Sparse.into_py(py)),
+                ("equity_iterations", 50.into_py(py)),
+                ("auto_topup_to", 300.into_py(py)),
+                ("max_actions_per_round", 100.into_py(py)),
+                ("observation_level", ObservationLevel::CommittedOnly.into_py(py)),
+                ("seed", 11.into_py(py)),
+                ("allin_ev_rewards", true.into_py(py)),
+                ("ante_mode", AnteMode::BigBlindAnte.into_py(py)),
+                ("debug_invariants", true.into_py(py)),
+                ("straddle", py.None()),
+                ("stacks", vec![400, 500, 600].into_py(py)),
+                ("mark_allin_calls", true.into_py(py)),
+                ("face_up_cards", 1.into_py(py)),
+                ("reward_scale", RewardScale::BigBlinds.into_py(py)),
+            ];
+            let kwargs = PyDict::new_bound(py);
+            for (key, value) in &options {
+                kwargs.set_item(key, value).unwrap();
             }
+            let config: PokerConfig = py.get_type_bound::<PokerConfig>().call((), Some(&kwargs)).unwrap().extract().unwrap();
+            let build = || PokerEnv::from_config(vec![py.None(); 3], config.clone()).unwrap();
+            let env = build();
 
-            i += 1;
-        }
+            assert_eq!(env.names, names);
+            assert_eq!(env.stacks.iter().sum::<i32>() + env.contributions.iter().sum::<i32>(), 1500);
+            assert_eq!((env.dealer_pos, env.small_blind, env.big_blind, env.ante), (1, 5, 10, 2));
+            assert_eq!((env.betting_mode.clone(), env.raise_cap, env.max_raises_per_street), (BettingMode::PotLimit, 3, Some(2)));
+            assert_eq!((env.base_deck.len(), env.board_schedule.clone()), (36, vec![3, 1, 1]));
+            assert_eq!(env.encoding, Encoding::OneHot);
+            assert_eq!(env.observation_level, ObservationLevel::CommittedOnly);
+            assert_eq!((env.rake_percent, env.rake_cap, env.payouts.clone()), (5.0, Some(3), Some(vec![0.7, 0.3])));
+            assert_eq!((env.reward_mode.clone(), env.reward_scale.clone()), (RewardMode::Sparse, RewardScale::BigBlinds));
+            assert_eq!((env.ante_mode.clone(), env.auto_topup_to, env.equity_iterations), (AnteMode::BigBlindAnte, Some(300), 50));
+            assert_eq!((env.agent_method.as_str(), env.max_actions_per_round, env.face_up_cards), ("act", 100, 1));
+            assert!(env.short_deck && env.record_history && env.run_it_twice && env.burn_cards && !env.rotate_button);
+            assert!(env.muck_losers && env.allin_ev_rewards && env.debug_invariants && env.mark_allin_calls);
+            // The big blind ante is posted by the big blind alone
+            assert_eq!(env.contributions.iter().sum::<i32>(), 2);
+            // The seed gives the same deal to every env of the config
+            assert_eq!(build().player_cards, env.player_cards);
+            // The constructor takes its blinds and initial stack over the ones of the config
+            let mut config: PokerConfig = py.get_type_bound::<PokerConfig>().call0().unwrap().extract().unwrap();
+            config.seed = Some(11);
+            let env = PokerEnv::new(py, vec![py.None(); 3], 10, 20, 1000, Some(config)).unwrap();
+            assert_eq!((env.small_blind, env.big_blind, env.initial_stack), (10, 20, 1000));
+            assert_eq!(env.stacks.iter().sum::<i32>() + env.contributions.iter().sum::<i32>(), 3000);
+        });
+    }
 
-        let mut j: i32 = 0;
-        while (j as usize) < self.num_players {
-            let agent_name = self.names[j as usize].clone();
-            self.stacks[j as usize] -= self.bets[j as usize];
-            if self.stacks[j as usize] == 0 {
-                if verbose {
-                    println!("{} lost", agent_name);
+    #[test]
+    fn first_preflop_actor_is_left_of_the_big_blind() {
+        with_py(|py| {
+            for (players, from_button) in [(3, 0), (4, 3), (6, 3)] {
+                let mut env = table(py, players, (1, 2), 100, &[]);
+                for _ in 0..players {
+                    env.reset(false).unwrap();
+                    let dealer = env.dealer_pos;
+                    assert_eq!((env.sb_pos(), env.bb_pos()), ((dealer + 1) % players, (dealer + 2) % players));
+                    assert_eq!(env.current_player, (dealer + from_button) % players, "{} players", players);
                 }
-                self.kill(j as usize)?;
-                j -= 1;
             }
-            j += 1;
-        }
-
-        if verbose {
-            println!("State of stacks: {:?}", self.stacks);
-            println!("{} player remaining", self.num_players);
-        }
+        });
+    }
 
-        if self.stacks.iter().sum::<i32>() + rest != stacks_before_resolution {
-            panic!("Number of stack is not correct anymore!");
-        }
+    #[test]
+    fn blinds_do_not_count_as_having_acted() {
+        with_py(|py| {
+            let mut env = table(py, 3, (1, 2), 100, &[]);
+            assert_eq!(env.has_acted_this_round, vec![false; 3]);
+            let first = env.current_player;
+            check_or_call(&mut env);
+            assert!(env.has_acted_this_round[first]);
+            assert_eq!(env.has_acted_this_round.iter().filter(|&&acted| acted).count(), 1);
 
-        Ok(())
+            // A new street starts with nobody having acted
+            check_or_call(&mut env);
+            check_or_call(&mut env);
+            assert_eq!(env.current_phase, Phase::Flop);
+            assert_eq!(env.has_acted_this_round, vec![false; 3]);
+        });
     }
 
-    /// Revive all player to play another game
-    pub fn revive(&mut self) -> PyResult<()> {
-        for a in self.dead_agents.clone() {
-            self.agents.push(a);
-        };
-        self.dead_agents = Vec::new();
-        for n in self.dead_names.clone() {
-            self.names.push(n)
-        };
-        self.dead_names = Vec::new();
-        self.num_players = self.agents.len();
+    #[test]
+    fn two_way_tie_on_an_odd_pot_rewards_the_odd_chip() {
+        with_py(|py| {
+            let options = [("dealer_pos", 0.into_py(py)), ("rotate_button", false.into_py(py))];
+            let mut env = table(py, 3, (1, 2), 100, &options);
+            let hands: [&[&str]; 3] = [&["2c", "3d"], &["Ac", "Ad"], &["2d", "3s"]];
+            env.rig_next_deal(rigged(&env, &hands, &["Ah", "Kd", "Qc", "Js", "Th"])).unwrap();
+            env.reset(false).unwrap();
+            // The button limps, the small blind folds his chip and the big blind checks
+            check_or_call(&mut env);
+            act(&mut env, Action::Fold, 0);
+            check_down(&mut env);
 
-        self.stacks = vec![self.initial_stack; self.num_players];
-        self.dealer_pos = 0;
+            // The pot of 5 is split on the board, the odd chip to the big blind, first left of the button
+            assert_eq!(env.rewards, vec![0, -1, 1]);
+            assert_eq!(env.rewards.iter().sum::<i32>(), 0);
+            assert_eq!(env.stacks, vec![100, 99, 101]);
+        });
+    }
 
-        self.reset()?;
+    #[test]
+    fn odd_chip_goes_to_the_same_seat_whatever_the_seed() {
+        with_py(|py| {
+            let play = |seed: u64| {
+                let options = [("dealer_pos", 0.into_py(py)), ("rotate_button", false.into_py(py)), ("seed", seed.into_py(py))];
+                let mut env = table(py, 4, (1, 2), 100, &options);
+                let hands: [&[&str]; 4] = [&["2c", "3d"], &["Ac", "Ad"], &["2d", "3s"], &["2s", "4d"]];
+                let deck = rigged(&env, &hands, &["Kh", "Qd", "Jc", "Ts", "9h"]);
+                env.rig_next_deal(deck).unwrap();
+                env.reset(false).unwrap();
+                check_or_call(&mut env);
+                check_or_call(&mut env);
+                act(&mut env, Action::Fold, 0);
+                check_down(&mut env);
+                (env.rewards.clone(), env.stacks.clone())
+            };
 
-        Ok(())
+            // Three ways on a pot of 7, the odd chip to the big blind, first winner left of the button
+            let outcome = play(1);
+            assert_eq!(outcome, (vec![0, -1, 1, 0], vec![100, 99, 101, 100]));
+            for seed in [1, 2, 3] {
+                assert_eq!(play(seed), outcome);
+            }
+        });
     }
 
-    /// play episode game(s) of poker
-    pub fn play_game(&mut self, episode: i32, verbose: bool) -> PyResult<()> {
-        let mut i = 1;
+    #[test]
+    fn survivor_of_a_river_bet_takes_the_pot_without_a_showdown() {
+        with_py(|py| {
+            let options = [("record_history", true.into_py(py))];
+            let mut env = table(py, 5, (1, 2), 100, &options);
+            env.reset(false).unwrap();
+            check_to_flop(&mut env);
+            while env.current_phase != Phase::River {
+                act(&mut env, Action::Check, 0);
+            }
+            let bettor = env.current_player;
+            act(&mut env, Action::Raise, 20);
+            fold_out(&mut env);
 
-        while i <= episode {
-            while self.num_players > 1 {
-                self.reset()?;
+            assert_eq!(env.terminal_reason().as_deref(), Some("all_folded"));
+            let expected: Vec<i32> = (0..5).map(|seat| if seat == bettor { 8 } else { -2 }).collect();
+            assert_eq!(env.rewards, expected);
+            assert_eq!(env.stacks.iter().sum::<i32>(), 500);
+            assert!(env.shown_hands.is_empty());
+            assert!(!env.history.iter().any(|(_, event)| matches!(event, Event::HandShown { .. })));
+        });
+    }
 
-                loop {
-                    if i % 1000 == 0 {
-                        println!("episode {} on {}", i, episode);
-                    }
+    #[test]
+    fn pickle_round_trip_keeps_the_game_and_the_next_deals() {
+        with_py(|py| {
+            let mut env = table(py, 4, (1, 2), 100, &[("seed", 21.into_py(py)), ("record_history", true.into_py(py))]);
+            env.reset(false).unwrap();
+            act(&mut env, Action::Raise, 6);
+            check_or_call(&mut env);
 
-                    if verbose {
-                        println!();
-                        self.overall_state()?;
-                    }
-                    i += 1;
+            // Pickle finds the class by its module, registered as an import of the extension would
+            let module = PyModule::new_bound(py, "rust_poker_env").unwrap();
+            rust_poker_env(py, &module).unwrap();
+            py.import_bound("sys").unwrap().getattr("modules").unwrap().set_item("rust_poker_env", module).unwrap();
+            let pickle = py.import_bound("pickle").unwrap();
+            let original = Bound::new(py, env).unwrap();
+            let dumped = pickle.call_method1("dumps", (&original,)).unwrap();
+            let copy: Py<PokerEnv> = pickle.call_method1("loads", (dumped,)).unwrap().extract().unwrap();
+            let (mut env, mut copy) = (original.borrow_mut(), copy.borrow_mut(py));
+            assert_eq!(copy.to_json().unwrap(), env.to_json().unwrap());
+            assert_eq!(copy.agents.len(), 4);
 
-                    if self.folded.iter().filter(|&&b| b).count() != self.num_players - 1 {
-                        self.step_bid(verbose)?;
-                    }
-                    self.advance_phase(verbose)?;
+            // The hand goes on the same, then the next deal
+            check_down(&mut env);
+            check_down(&mut copy);
+            assert_eq!((copy.community_cards.clone(), copy.stacks.clone()), (env.community_cards.clone(), env.stacks.clone()));
+            env.reset(false).unwrap();
+            copy.reset(false).unwrap();
+            assert_eq!(copy.player_cards, env.player_cards);
+            assert_eq!(copy.deck, env.deck);
+        });
+    }
 
-                    if self.current_phase == Phase::Showdown {
-                        if verbose {
-                            println!();
-                            self.overall_state()?;
-                        }
+    #[test]
+    fn validate_finds_a_card_lost_or_doubled() {
+        with_py(|py| {
+            let mut env = table(py, 3, (1, 2), 100, &[("burn_cards", true.into_py(py)), ("run_it_twice", true.into_py(py))]);
+            env.reset(false).unwrap();
+            check_or_call(&mut env);
+            check_or_call(&mut env);
+            act(&mut env, Action::Check, 0);
+            env.validate().unwrap();
 
-                        self.resolution(verbose)?;
-                        break;
-                    }
-                }
-            }
-            self.revive()?;
-        }
+            let mut lost = env.clone();
+            let card = lost.deck.remove(0);
+            let error = lost.validate().unwrap_err().to_string();
+            assert!(error.contains(&format!("missing [{:?}]", card)), "{}", error);
 
-        Ok(())
+            let mut doubled = env.clone();
+            doubled.deck[0] = doubled.player_cards[0][0].clone();
+            assert!(doubled.validate().unwrap_err().to_string().contains("duplicate"));
+
+            // Players busted at the showdown leave with their cards
+            all_in_to_showdown(&mut env);
+            env.resolution(false).unwrap();
+            env.validate().unwrap();
+        });
     }
 }
-
-#[pymodule]
-fn rust_poker_env(_py: Python, m: &Bound<'_, PyModule>) -> PyResult<()> {
-    m.add_class::<Action>()?;
-    m.add_class::<Phase>()?;
-    m.add_class::<PokerEnv>()?;
-    Ok(())
-}
\ No newline at end of file